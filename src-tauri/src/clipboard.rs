@@ -1,37 +1,384 @@
-use clipboard_rs::{ClipboardContext, Clipboard, ContentFormat};
-use crate::storage::SharedStorage;
+use base64::Engine;
+use clipboard_rs::{ClipboardContent, ClipboardContext, Clipboard, ContentFormat};
+use crate::clock::now_secs;
+use crate::events;
+use crate::i18n;
+use crate::platform::get_platform_adapter;
+use crate::storage::{
+    lock_storage, read_storage, AddedItem, CaptureKind, CaptureSource, ClipboardItem, ExtraFormat, SharedStorage,
+};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use thiserror::Error;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 
 #[derive(Error, Debug)]
 pub enum ClipboardError {
-    #[error("剪切板操作失败: {0}")]
+    #[error("{}", i18n::tf("error.clipboard_op", &[.0]))]
     ClipboardError(String),
-    #[error("存储操作失败: {0}")]
+    #[error("{}", i18n::tf("error.storage_op", &[.0]))]
     StorageError(String),
-    #[error("内容过大")]
-    ContentTooLarge,
-    #[error("无效操作")]
-    InvalidOperation,
+}
+
+impl ClipboardError {
+    /// 稳定的错误类别，不随语言变化
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClipboardError::ClipboardError(_) => "clipboard",
+            ClipboardError::StorageError(_) => "storage",
+        }
+    }
+}
+
+type ClipboardResult<T> = Result<T, ClipboardError>;
+
+// 剪切板可能被其他程序短暂占用（Windows 上 OpenClipboard 失败很常见），按退避间隔重试
+const RETRY_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY_MS: u64 = 50;
+
+// 重试剪切板操作，两次重试共等待约150ms，仍失败则返回最后一次的错误
+pub fn with_retry<T, E: std::fmt::Display>(
+    action: &str,
+    mut op: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < RETRY_ATTEMPTS => {
+                let delay = RETRY_BASE_DELAY_MS << (attempt - 1);
+                eprintln!("{}失败（第{}次）: {}，{}ms后重试", action, attempt, e, delay);
+                std::thread::sleep(std::time::Duration::from_millis(delay));
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// 剪切板访问的抽象，监控逻辑只依赖该接口，便于替换为内存实现
+pub trait ClipboardBackend: Send {
+    fn get_text(&self) -> ClipboardResult<String>;
+    fn set_text(&self, text: String) -> ClipboardResult<()>;
+    fn has(&self, format: ContentFormat) -> bool;
+
+    // 剪切板变更序列号，返回None时每次都读取内容比较
+    fn sequence(&self) -> Option<u64> {
+        None
+    }
+
+    // 内容是否来自本应用的静默写入，是则不记录
+    fn is_expected_write(&self, _content: &str) -> bool {
+        false
+    }
+
+    // 读取指定名称的自定义格式，不支持或不存在时返回None
+    fn get_buffer(&self, _format: &str) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+impl ClipboardBackend for ClipboardContext {
+    fn get_text(&self) -> ClipboardResult<String> {
+        Clipboard::get_text(self).map_err(|e| ClipboardError::ClipboardError(e.to_string()))
+    }
+
+    fn set_text(&self, text: String) -> ClipboardResult<()> {
+        with_retry("写入剪切板", || Clipboard::set_text(self, text.clone()))
+            .map_err(|e| ClipboardError::ClipboardError(e.to_string()))
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        Clipboard::has(self, format)
+    }
+
+    fn sequence(&self) -> Option<u64> {
+        get_platform_adapter().clipboard_sequence()
+    }
+
+    fn get_buffer(&self, format: &str) -> Option<Vec<u8>> {
+        Clipboard::get_buffer(self, format).ok()
+    }
+}
+
+// 全应用共享的剪切板上下文，命令与后台监控复用同一个，避免反复打开剪切板带来的开销和争用
+#[derive(Default)]
+pub struct ClipboardService {
+    ctx: Mutex<Option<ClipboardContext>>,
+    // 静默写入的内容，监控读到它时不记录
+    expected_write: Mutex<Option<String>>,
+    // 写入 PRIMARY 选区用的句柄；X11 下选区由持有者提供，需保持存活
+    #[cfg(target_os = "linux")]
+    primary: Mutex<Option<arboard::Clipboard>>,
+}
+
+// 类型别名，便于在 Tauri 命令中使用
+pub type SharedClipboard = Arc<ClipboardService>;
+
+// 系统剪切板当前内容的概要，只读取不记录
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardPeek {
+    pub text: Option<String>,
+    pub has_image: bool,
+    pub has_files: bool,
+}
+
+impl ClipboardService {
+    // 持有上下文执行操作；首次使用时才打开剪切板，打开失败时下次调用会重新尝试
+    pub fn with_context<T>(
+        &self,
+        op: impl FnOnce(&ClipboardContext) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut guard = self.ctx.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let ctx = match guard.take() {
+            Some(ctx) => ctx,
+            None => with_retry("打开剪切板", ClipboardContext::new)
+                .map_err(|e| i18n::tf("error.create_clipboard", &[&e]))?,
+        };
+        op(&*guard.insert(ctx))
+    }
+
+    // 读取剪切板文本，内容不是文本时返回 None
+    pub fn get_text(&self) -> Result<Option<String>, String> {
+        self.with_context(|ctx| {
+            if !ctx.has(ContentFormat::Text) {
+                return Ok(None);
+            }
+            with_retry("读取剪切板", || ctx.get_text())
+                .map(Some)
+                .map_err(|e| i18n::tf("error.read_clipboard", &[&e]))
+        })
+    }
+
+    // 查看剪切板当前有哪些格式，不写入历史；剪切板为空时返回 None
+    pub fn peek(&self) -> Result<Option<ClipboardPeek>, String> {
+        self.with_context(|ctx| {
+            let text = if ctx.has(ContentFormat::Text) {
+                Some(
+                    with_retry("读取剪切板", || ctx.get_text())
+                        .map_err(|e| i18n::tf("error.read_clipboard", &[&e]))?,
+                )
+            } else {
+                None
+            };
+            let has_image = ctx.has(ContentFormat::Image);
+            let has_files = ctx.has(ContentFormat::Files);
+
+            if text.is_none() && !has_image && !has_files {
+                return Ok(None);
+            }
+            Ok(Some(ClipboardPeek {
+                text,
+                has_image,
+                has_files,
+            }))
+        })
+    }
+
+    // 剪切板当前内容提供的格式，用于排查复制未被记录的原因（如只有图片时读取文本会失败）
+    pub fn formats(&self) -> Result<Vec<String>, String> {
+        self.with_context(|ctx| {
+            let formats = [
+                (ContentFormat::Text, "text"),
+                (ContentFormat::Image, "image"),
+                (ContentFormat::Html, "html"),
+                (ContentFormat::Files, "files"),
+            ];
+            Ok(formats
+                .into_iter()
+                .filter_map(|(format, name)| ctx.has(format).then(|| name.to_string()))
+                .collect())
+        })
+    }
+
+    pub fn set_text(&self, text: &str) -> Result<(), String> {
+        self.with_context(|ctx| {
+            with_retry("写入剪切板", || ctx.set_text(text.to_string()))
+                .map_err(|e| i18n::tf("error.set_clipboard", &[&e]))
+        })
+    }
+
+    // 同时写入文本和自定义格式，供需要专用格式的程序粘贴
+    pub fn set_text_with_formats(&self, text: &str, formats: &[(String, Vec<u8>)]) -> Result<(), String> {
+        let contents = || {
+            std::iter::once(ClipboardContent::Text(text.to_string()))
+                .chain(
+                    formats
+                        .iter()
+                        .map(|(mime, data)| ClipboardContent::Other(mime.clone(), data.clone())),
+                )
+                .collect::<Vec<_>>()
+        };
+        self.with_context(|ctx| {
+            with_retry("写入剪切板", || ctx.set(contents()))
+                .map_err(|e| i18n::tf("error.set_clipboard", &[&e]))
+        })
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.with_context(|ctx| {
+            with_retry("清空剪切板", || ctx.clear())
+                .map_err(|e| i18n::tf("error.clear_clipboard", &[&e]))
+        })
+    }
+
+    // 写入 X11 的 PRIMARY 选区，之后中键即可粘贴；没有该选区的平台返回错误
+    #[cfg(target_os = "linux")]
+    pub fn set_primary_text(&self, text: &str) -> Result<(), String> {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return Err(i18n::t("error.primary_selection_unsupported").to_string());
+        }
+
+        let mut guard = self.primary.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let clipboard = match guard.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()
+                .map_err(|e| i18n::tf("error.create_clipboard", &[&e]))?,
+        };
+        guard
+            .insert(clipboard)
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text.to_string())
+            .map_err(|e| i18n::tf("error.set_clipboard", &[&e]))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn set_primary_text(&self, _text: &str) -> Result<(), String> {
+        Err(i18n::t("error.primary_selection_unsupported").to_string())
+    }
+
+    // 写入剪切板，并登记为预期写入，监控不会把它记录到历史
+    pub fn set_text_silent(&self, text: &str) -> Result<(), String> {
+        *self.expected_write.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(text.to_string());
+        let result = self.set_text(text);
+        if result.is_err() {
+            self.expected_write.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        }
+        result
+    }
+
+    // 消费预期写入标记；监控每读到一次新内容都会清除它，避免误伤之后真正的复制
+    pub fn take_expected_write(&self, content: &str) -> bool {
+        let mut expected = self.expected_write.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        expected.take().as_deref() == Some(content)
+    }
+}
+
+impl ClipboardBackend for SharedClipboard {
+    fn get_text(&self) -> ClipboardResult<String> {
+        // 轮询本身就是重试，这里不再额外等待
+        self.with_context(|ctx| ctx.get_text().map_err(|e| e.to_string()))
+            .map_err(ClipboardError::ClipboardError)
+    }
+
+    fn set_text(&self, text: String) -> ClipboardResult<()> {
+        ClipboardService::set_text(self, &text).map_err(ClipboardError::ClipboardError)
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        self.with_context(|ctx| Ok(ctx.has(format))).unwrap_or(false)
+    }
+
+    fn sequence(&self) -> Option<u64> {
+        get_platform_adapter().clipboard_sequence()
+    }
+
+    fn is_expected_write(&self, content: &str) -> bool {
+        self.take_expected_write(content)
+    }
+
+    fn get_buffer(&self, format: &str) -> Option<Vec<u8>> {
+        self.with_context(|ctx| ctx.get_buffer(format).map_err(|e| e.to_string())).ok()
+    }
+}
+
+// 纯内存的剪切板，不访问系统剪切板，用于在无图形环境下测试监控逻辑
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryClipboard {
+    text: Mutex<Option<String>>,
+}
+
+#[cfg(test)]
+impl MemoryClipboard {
+    pub fn new(text: Option<String>) -> Self {
+        Self {
+            text: Mutex::new(text),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ClipboardBackend for MemoryClipboard {
+    fn get_text(&self) -> ClipboardResult<String> {
+        let text = self.text.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        text.clone()
+            .ok_or_else(|| ClipboardError::ClipboardError("剪切板中没有文本".to_string()))
+    }
+
+    fn set_text(&self, text: String) -> ClipboardResult<()> {
+        let mut current = self.text.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = Some(text);
+        Ok(())
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        matches!(format, ContentFormat::Text)
+            && self.text.lock().map(|text| text.is_some()).unwrap_or(false)
+    }
+}
+
+// 后台监控线程是否在运行
+static MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// 监控最近一次读到新内容的时间与长度；出于隐私只保存长度
+static LAST_CAPTURE: Mutex<Option<(Instant, usize)>> = Mutex::new(None);
+
+// 监控状态，用于排查“停止记录”之类的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorStatus {
+    pub running: bool,
+    pub last_capture_secs_ago: Option<u64>,
+    pub last_content_len: Option<usize>,
+}
+
+pub fn monitor_status() -> MonitorStatus {
+    let last_capture = *LAST_CAPTURE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    MonitorStatus {
+        running: MONITOR_RUNNING.load(Ordering::SeqCst),
+        last_capture_secs_ago: last_capture.map(|(at, _)| at.elapsed().as_secs()),
+        last_content_len: last_capture.map(|(_, len)| len),
+    }
 }
 
 pub struct SimpleClipboardMonitor {
-    ctx: ClipboardContext,
+    ctx: Box<dyn ClipboardBackend>,
     last_content: Option<String>,
+    // 上次读取时的剪切板序列号，未变化时跳过读取
+    last_sequence: Option<u64>,
     storage: SharedStorage,
     is_running: bool,
 }
 
-type ClipboardResult<T> = Result<T, ClipboardError>;
-
 impl SimpleClipboardMonitor {
-    pub fn new(storage: SharedStorage) -> ClipboardResult<Self> {
-        Ok(Self {
-            ctx: ClipboardContext::new().map_err(|e| ClipboardError::ClipboardError(e.to_string()))?,
+    pub fn new(storage: SharedStorage, clipboard: SharedClipboard) -> Self {
+        Self::with_backend(storage, Box::new(clipboard))
+    }
+
+    // 使用指定的剪切板实现创建监控器
+    pub fn with_backend(storage: SharedStorage, backend: Box<dyn ClipboardBackend>) -> Self {
+        Self {
+            ctx: backend,
             last_content: None,
+            last_sequence: None,
             storage,
             is_running: false,
-        })
+        }
     }
 
     pub fn start_monitoring(&mut self) {
@@ -49,12 +396,28 @@ impl SimpleClipboardMonitor {
             return None;
         }
 
+        let sequence = self.ctx.sequence();
+        if sequence.is_some() && sequence == self.last_sequence {
+            return None;
+        }
+        self.last_sequence = sequence;
+
         match self.ctx.get_text() {
             Ok(content) => {
                 // 检查是否有变化
                 if Some(&content) != self.last_content.as_ref() {
-                    // 检查大文本限制
-                    if content.len() <= 1024 * 1024 { // 1MB 限制
+                    if self.ctx.is_expected_write(&content) {
+                        self.last_content = Some(content);
+                        return None;
+                    }
+                    // 检查大文本限制，用户临时放开时允许记录一次
+                    let storage = read_storage(&self.storage);
+                    let allowed = content.len() <= storage.data.settings.limit_for(CaptureKind::Text)
+                        || storage.oversize_capture_armed();
+                    drop(storage);
+                    if allowed {
+                        *LAST_CAPTURE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                            Some((Instant::now(), content.len()));
                         self.last_content = Some(content.clone());
                         return Some(content);
                     } else {
@@ -69,8 +432,7 @@ impl SimpleClipboardMonitor {
     }
 
     pub fn set_content(&mut self, content: &str) -> ClipboardResult<()> {
-        self.ctx.set_text(content.to_string())
-            .map_err(|e| ClipboardError::ClipboardError(e.to_string()))?;
+        self.ctx.set_text(content.to_string())?;
         self.last_content = Some(content.to_string());
         Ok(())
     }
@@ -79,42 +441,190 @@ impl SimpleClipboardMonitor {
         self.ctx.has(ContentFormat::Text)
     }
 
-    pub fn process_clipboard_change(&mut self, content: String) -> ClipboardResult<Option<u64>> {
-        if let Ok(mut storage) = self.storage.lock() {
-            let item_id = storage.add_item(content)
+    pub fn process_clipboard_change(&mut self, content: String, source: CaptureSource) -> ClipboardResult<Option<AddedItem>> {
+        if !read_storage(&self.storage).data.settings.history_enabled {
+            dev_log!("历史记录已关闭，仅预览不保存");
+            return Ok(None);
+        }
+
+        // 在取得写锁之前读取自定义格式，避免读取剪切板期间阻塞其他命令
+        let extra_formats = match source {
+            CaptureSource::Clipboard => self.read_extra_formats(),
+            _ => Vec::new(),
+        };
+
+        let mut storage = lock_storage(&self.storage);
+        if !storage.should_capture(&content) {
+            dev_log!("内容类型未启用或不匹配仅记录规则，已跳过");
+            return Ok(None);
+        }
+        let added = storage.add_item_from(content, source)
+            .map_err(|e| ClipboardError::StorageError(e.to_string()))?;
+        if !extra_formats.is_empty() {
+            storage
+                .set_extra_formats(added.id, extra_formats)
                 .map_err(|e| ClipboardError::StorageError(e.to_string()))?;
-            dev_log!("剪切板项目已添加: ID {}", item_id);
-            Ok(Some(item_id))
-        } else {
-            Err(ClipboardError::StorageError("无法访问存储".to_string()))
         }
+        dev_log!("剪切板项目已添加: ID {}", added.id);
+        Ok(Some(added))
+    }
+
+    // 读取设置中允许记录的自定义格式，总大小超过文本大小上限时不再继续读取
+    fn read_extra_formats(&self) -> Vec<ExtraFormat> {
+        let (mimes, limit) = {
+            let storage = read_storage(&self.storage);
+            let settings = &storage.data.settings;
+            (settings.capture_custom_formats.clone(), settings.limit_for(CaptureKind::Text))
+        };
+        let mut total = 0;
+        let mut formats = Vec::new();
+        for mime in mimes {
+            let Some(data) = self.ctx.get_buffer(&mime) else {
+                continue;
+            };
+            total += data.len();
+            if total > limit {
+                dev_log!("自定义格式数据过大，已跳过: {}", mime);
+                break;
+            }
+            formats.push(ExtraFormat {
+                base64: base64::engine::general_purpose::STANDARD.encode(data),
+                mime,
+            });
+        }
+        formats
+    }
+
+    // 记录一次捕获，并把存储中的完整项目发送给前端；与最新条目重复或未记录时返回 None
+    fn record_capture(
+        &mut self,
+        content: String,
+        source: CaptureSource,
+        app_handle: Option<&tauri::AppHandle>,
+    ) -> Option<ClipboardItem> {
+        if !read_storage(&self.storage).data.settings.history_enabled {
+            // 只推送实时内容供预览，不写入历史
+            if let Some(app) = app_handle {
+                let _ = app.emit(
+                    events::CLIPBOARD_PREVIEW,
+                    events::ClipboardPreviewPayload { content: &content, timestamp: now_secs() },
+                );
+            }
+            return None;
+        }
+        let added = self.process_clipboard_change(content.clone(), source).ok().flatten()?;
+        if added.duplicate {
+            return None;
+        }
+        // 取存储中的完整项目，保证与列表数据一致
+        let stored_item = read_storage(&self.storage).get_item_by_id(added.id).cloned()?;
+
+        // 如果有事件通知，发送到前端
+        if let Some(app) = app_handle {
+            if added.oversize {
+                let _ = app.emit(events::OVERSIZE_CAPTURED, stored_item.id);
+            }
+            let _ = app.emit(
+                events::CLIPBOARD_ITEM_ADDED,
+                events::ItemAddedPayload { item: &stored_item },
+            );
+            let _ = app.emit(events::CLIPBOARD_UPDATED, stored_item.clone());
+            dev_log!("已发送剪切板更新事件: {}", content.chars().take(50).collect::<String>());
+        }
+        Some(stored_item)
     }
 
     fn show_large_text_notification(&self) {
-        dev_log!("警告：不支持监控大于1MB的文本内容");
+        dev_log!("警告：文本内容超过大小上限（max_content_kb），未记录");
         // TODO: 这里可以使用 Tauri API 显示系统通知
     }
 }
 
-// 用于后台监控的函数
-pub fn start_clipboard_monitoring(storage: SharedStorage) -> ClipboardResult<()> {
-    start_clipboard_monitoring_with_events(storage, None)
+// 手动“立即记录”当前剪切板：与后台监控走同一条记录流程（去重、过滤、自定义格式、超限事件），
+// 不论监控是否开启都会执行；内容为空、不是文本、与最新记录相同或被过滤时返回 None
+pub fn capture_once(
+    storage: SharedStorage,
+    clipboard: SharedClipboard,
+    app_handle: Option<&tauri::AppHandle>,
+) -> Option<ClipboardItem> {
+    let content = ClipboardService::get_text(&clipboard).ok().flatten()?;
+    if content.trim().is_empty() {
+        return None;
+    }
+    SimpleClipboardMonitor::new(storage, clipboard).record_capture(content, CaptureSource::Clipboard, app_handle)
 }
 
-// 用于后台监控的函数，支持事件通知
-pub fn start_clipboard_monitoring_with_events(storage: SharedStorage, app_handle: Option<tauri::AppHandle>) -> ClipboardResult<()> {
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
+// X11 的 PRIMARY 选区（选中即复制、中键粘贴）；Wayland、Windows、macOS 上没有对应概念，始终不返回内容
+#[derive(Default)]
+pub struct PrimarySelectionWatcher {
+    #[cfg(target_os = "linux")]
+    clipboard: Option<arboard::Clipboard>,
+    last_content: Option<String>,
+}
 
-    static MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+impl PrimarySelectionWatcher {
+    // 选区内容有变化时返回新内容，超过 max_bytes 的内容忽略
+    pub fn check_for_changes(&mut self, max_bytes: usize) -> Option<String> {
+        let content = self.read()?;
+        if content.trim().is_empty()
+            || content.len() > max_bytes
+            || Some(&content) == self.last_content.as_ref()
+        {
+            return None;
+        }
+        self.last_content = Some(content.clone());
+        Some(content)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read(&mut self) -> Option<String> {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return None;
+        }
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        self.clipboard
+            .as_mut()?
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            .ok()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read(&mut self) -> Option<String> {
+        None
+    }
+}
+
+// 自身窗口处于前台时，剪切板变化来自本应用的界面操作（如搜索框中复制），不应记录
+fn own_window_focused(app: &tauri::AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
+
+// 用于后台监控的函数
+pub fn start_clipboard_monitoring(storage: SharedStorage, clipboard: SharedClipboard) -> ClipboardResult<()> {
+    start_clipboard_monitoring_with_events(storage, clipboard, None)
+}
 
+// 用于后台监控的函数，支持事件通知
+pub fn start_clipboard_monitoring_with_events(
+    storage: SharedStorage,
+    clipboard: SharedClipboard,
+    app_handle: Option<tauri::AppHandle>,
+) -> ClipboardResult<()> {
     // 防止在开发模式下启动多个监控线程
     if MONITOR_RUNNING.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
         dev_log!("剪切板监控已在运行中，跳过重复启动");
         return Ok(());
     }
 
-    let mut monitor = SimpleClipboardMonitor::new(storage.clone())?;
+    let mut monitor = SimpleClipboardMonitor::new(storage.clone(), clipboard);
     monitor.start_monitoring();
 
     let _storage_clone = storage.clone();
@@ -126,6 +636,9 @@ pub fn start_clipboard_monitoring_with_events(storage: SharedStorage, app_handle
         let thread_id = std::thread::current().id();
         dev_log!("启动剪切板监控线程: {:?}", thread_id);
 
+        // 选区读取器需在监控线程内创建和使用
+        let mut primary_selection = PrimarySelectionWatcher::default();
+
         loop {
             // 检查是否应该停止
             if stop_flag_clone.load(Ordering::SeqCst) {
@@ -135,27 +648,23 @@ pub fn start_clipboard_monitoring_with_events(storage: SharedStorage, app_handle
             }
 
             if let Some(content) = monitor.check_for_changes() {
-                if let Ok(Some(item_id)) = monitor.process_clipboard_change(content.clone()) {
-                    // 如果有事件通知，发送到前端
-                    if let Some(ref app) = app_handle {
-                        use crate::storage::ClipboardItem;
-
-                        // 构建剪切板项目
-                        let timestamp = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs();
-
-                        let clipboard_item = ClipboardItem {
-                            id: item_id,
-                            content: content.clone(),
-                            timestamp,
-                            is_favorite: false,
-                        };
-
-                        // 发送事件到前端
-                        let _ = app.emit("clipboard-updated", clipboard_item);
-                        dev_log!("已发送剪切板更新事件: {}", content.chars().take(50).collect::<String>());
+                if app_handle.as_ref().is_some_and(own_window_focused) {
+                    // 内容已记为最近一次，窗口失焦后也不会被补录
+                    dev_log!("剪切板管理器窗口处于前台，忽略本次复制");
+                } else {
+                    monitor.record_capture(content, CaptureSource::Clipboard, app_handle.as_ref());
+                }
+            }
+
+            let (capture_primary, max_bytes) = {
+                let storage = read_storage(&monitor.storage);
+                let settings = &storage.data.settings;
+                (settings.capture_primary_selection, settings.limit_for(CaptureKind::Text))
+            };
+            if capture_primary {
+                if let Some(content) = primary_selection.check_for_changes(max_bytes) {
+                    if !app_handle.as_ref().is_some_and(own_window_focused) {
+                        monitor.record_capture(content, CaptureSource::PrimarySelection, app_handle.as_ref());
                     }
                 }
             }
@@ -172,4 +681,96 @@ pub fn start_clipboard_monitoring_with_events(storage: SharedStorage, app_handle
 
     dev_log!("剪切板监控已安全启动");
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{CaptureKind, SimpleStorage};
+    use std::sync::RwLock;
+
+    fn test_monitor(text: Option<&str>) -> SimpleClipboardMonitor {
+        let storage = Arc::new(RwLock::new(SimpleStorage::in_temp_dir()));
+        let backend = MemoryClipboard::new(text.map(str::to_string));
+        let mut monitor = SimpleClipboardMonitor::with_backend(storage, Box::new(backend));
+        monitor.start_monitoring();
+        monitor
+    }
+
+    fn cleanup(monitor: SimpleClipboardMonitor) {
+        read_storage(&monitor.storage).remove_temp_dir();
+    }
+
+    #[test]
+    fn reports_only_changed_content() {
+        let mut monitor = test_monitor(Some("first"));
+        assert_eq!(monitor.check_for_changes().as_deref(), Some("first"));
+        assert_eq!(monitor.check_for_changes(), None);
+
+        monitor.ctx.set_text("second".into()).unwrap();
+        assert_eq!(monitor.check_for_changes().as_deref(), Some("second"));
+        assert_eq!(monitor.check_for_changes(), None);
+
+        cleanup(monitor);
+    }
+
+    #[test]
+    fn ignores_empty_clipboard_and_stopped_monitor() {
+        let mut monitor = test_monitor(None);
+        assert_eq!(monitor.check_for_changes(), None);
+
+        monitor.ctx.set_text("text".into()).unwrap();
+        monitor.stop_monitoring();
+        assert_eq!(monitor.check_for_changes(), None);
+
+        cleanup(monitor);
+    }
+
+    #[test]
+    fn respects_size_limit_and_one_time_override() {
+        let mut monitor = test_monitor(Some(&"x".repeat(2048)));
+        lock_storage(&monitor.storage)
+            .data
+            .settings
+            .max_content_kb
+            .insert(CaptureKind::Text, 1);
+        assert_eq!(monitor.check_for_changes(), None);
+
+        // 内容未变化也会重新检查，临时放开后可以记录一次
+        monitor.ctx.set_text("y".repeat(2048)).unwrap();
+        lock_storage(&monitor.storage).arm_oversize_capture();
+        assert_eq!(monitor.check_for_changes().map(|content| content.len()), Some(2048));
+
+        cleanup(monitor);
+    }
+
+    #[test]
+    fn repeated_capture_reuses_item() {
+        let mut monitor = test_monitor(None);
+        let first = monitor
+            .process_clipboard_change("same".into(), CaptureSource::Clipboard)
+            .unwrap();
+        let second = monitor
+            .process_clipboard_change("same".into(), CaptureSource::Clipboard)
+            .unwrap();
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.id, second.id);
+        assert!(!first.duplicate && second.duplicate);
+        assert_eq!(read_storage(&monitor.storage).data.items.len(), 1);
+
+        cleanup(monitor);
+    }
+
+    #[test]
+    fn record_capture_skips_duplicates() {
+        let mut monitor = test_monitor(None);
+        let first = monitor.record_capture("note".into(), CaptureSource::Clipboard, None);
+        assert_eq!(first.map(|item| item.content).as_deref(), Some("note"));
+        assert!(monitor.record_capture("note".into(), CaptureSource::Clipboard, None).is_none());
+
+        lock_storage(&monitor.storage).data.settings.history_enabled = false;
+        assert!(monitor.record_capture("other".into(), CaptureSource::Clipboard, None).is_none());
+        assert_eq!(read_storage(&monitor.storage).data.items.len(), 1);
+
+        cleanup(monitor);
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Datelike, Local};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, SystemTimeError, UNIX_EPOCH};
+
+use crate::i18n::{t, tf, tf_count};
+
+// 从未读到过正常时间时的估算起点（2024-01-01 UTC），保证时间戳不会退化为 0
+const FALLBACK_EPOCH_SECS: u64 = 1_704_067_200;
+
+// 最近一次正常读数及其对应的单调时钟时刻
+static LAST_GOOD: Mutex<Option<(Instant, u64)>> = Mutex::new(None);
+
+// 当前 Unix 时间（秒）。系统时钟早于 1970 年（虚拟机或主板电池异常）时，
+// 以最近一次正常读数加上单调时钟经过的时间估算，避免条目时间戳为 0 而排到列表末尾
+pub fn now_secs() -> u64 {
+    secs_from_reading(&LAST_GOOD, SystemTime::now().duration_since(UNIX_EPOCH))
+}
+
+// 由系统时钟读数换算时间；读数与最近正常读数的存放位置都由调用方传入，便于测试模拟时钟异常
+fn secs_from_reading(
+    last_good: &Mutex<Option<(Instant, u64)>>,
+    reading: Result<Duration, SystemTimeError>,
+) -> u64 {
+    let mut last_good = last_good
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match reading {
+        Ok(elapsed) => {
+            let secs = elapsed.as_secs();
+            *last_good = Some((Instant::now(), secs));
+            secs
+        }
+        Err(e) => {
+            eprintln!("系统时间异常，使用估算时间: {}", e);
+            let (at, secs) = *last_good.get_or_insert((Instant::now(), FALLBACK_EPOCH_SECS));
+            secs + at.elapsed().as_secs()
+        }
+    }
+}
+
+// 按系统时区把时间戳格式化为相对时间，如“2 分钟前”“昨天 14:32”。
+// 按本地日历日比较，夏令时切换当天也能正确判断“昨天”
+pub fn format_timestamp(ts: u64) -> String {
+    let time = match DateTime::from_timestamp(ts as i64, 0) {
+        Some(time) => time.with_timezone(&Local),
+        None => return String::new(),
+    };
+    let now = DateTime::from_timestamp(now_secs() as i64, 0)
+        .map(|now| now.with_timezone(&Local))
+        .unwrap_or_else(Local::now);
+
+    // 略晚于当前时间（时钟回拨）也视为刚刚
+    let elapsed = (now - time).num_seconds().max(0);
+    let today = now.date_naive();
+    let date = time.date_naive();
+
+    if elapsed < 60 {
+        t("time.just_now").to_string()
+    } else if elapsed < 3600 {
+        tf_count("time.minute_ago", "time.minutes_ago", elapsed / 60)
+    } else if date == today {
+        tf_count("time.hour_ago", "time.hours_ago", elapsed / 3600)
+    } else if today.pred_opt() == Some(date) {
+        tf("time.yesterday", &[&time.format("%H:%M")])
+    } else if date.year() == today.year() {
+        time.format("%m-%d %H:%M").to_string()
+    } else {
+        time.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 构造一个“早于 1970 年”的时钟读数
+    fn failed_reading() -> Result<Duration, SystemTimeError> {
+        UNIX_EPOCH.duration_since(UNIX_EPOCH + Duration::from_secs(1))
+    }
+
+    #[test]
+    fn good_reading_is_used_as_is() {
+        let last_good = Mutex::new(None);
+        assert_eq!(secs_from_reading(&last_good, Ok(Duration::from_secs(1_800_000_000))), 1_800_000_000);
+    }
+
+    #[test]
+    fn failure_before_any_good_reading_uses_fallback_epoch() {
+        let last_good = Mutex::new(None);
+        let secs = secs_from_reading(&last_good, failed_reading());
+        assert!(secs >= FALLBACK_EPOCH_SECS && secs < FALLBACK_EPOCH_SECS + 5, "{}", secs);
+        // 之后的估算从同一起点继续，不会倒退
+        assert!(secs_from_reading(&last_good, failed_reading()) >= secs);
+    }
+
+    #[test]
+    fn failure_after_good_reading_continues_from_it() {
+        let last_good = Mutex::new(None);
+        secs_from_reading(&last_good, Ok(Duration::from_secs(1_800_000_000)));
+        let secs = secs_from_reading(&last_good, failed_reading());
+        assert!((1_800_000_000..1_800_000_005).contains(&secs), "{}", secs);
+    }
+
+    #[test]
+    fn now_secs_is_never_zero() {
+        assert!(now_secs() > 0);
+    }
+}
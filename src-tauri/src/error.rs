@@ -0,0 +1,78 @@
+use crate::clipboard::ClipboardError;
+use crate::i18n;
+use crate::storage::StorageError;
+use serde::Serialize;
+use std::fmt;
+
+/// 返回给前端的命令错误：kind 为稳定的类别标识，前端据此区分处理；
+/// message 为当前语言下可直接展示的提示
+#[derive(Debug, Serialize)]
+pub struct CommandError {
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(kind: &'static str, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+
+    /// 用给定的消息模板包装存储错误，类别沿用存储错误本身
+    pub fn storage(key: &str, err: StorageError) -> Self {
+        Self::new(err.kind(), i18n::tf(key, &[&err]))
+    }
+
+    pub fn not_found() -> Self {
+        Self::new("not_found", i18n::t("error.item_not_found"))
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new("invalid_input", message)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<StorageError> for CommandError {
+    fn from(err: StorageError) -> Self {
+        Self::new(err.kind(), err.to_string())
+    }
+}
+
+impl From<ClipboardError> for CommandError {
+    fn from(err: ClipboardError) -> Self {
+        Self::new(err.kind(), err.to_string())
+    }
+}
+
+// 其余仍以字符串返回的错误（窗口、系统调用等）归为 other
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new("other", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+
+    // 不切换全局语言，避免与并行运行、断言本地化消息的测试互相干扰
+    #[test]
+    fn kind_is_stable_across_locales() {
+        let err = CommandError::storage("error.add_item", StorageError::TooLarge);
+        assert_eq!(err.kind, "too_large");
+        assert_eq!(err.message, i18n::tf("error.add_item", &[&StorageError::TooLarge]));
+
+        let message = |locale| {
+            let reason = i18n::t_in(locale, "error.content_too_large");
+            i18n::tf_in(locale, "error.add_item", &[&reason])
+        };
+        assert_eq!(message(Locale::En), "Failed to add item: Content is too large (over the size limit)");
+        assert_eq!(message(Locale::ZhCn), "新增记录失败: 内容过大，超过大小上限");
+    }
+}
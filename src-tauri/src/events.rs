@@ -0,0 +1,66 @@
+// 发送给前端的事件名称，统一在此定义，避免各模块之间字符串拼写不一致
+
+use serde::Serialize;
+
+use crate::storage::ClipboardItem;
+
+// 窗口与页面切换
+pub const SHOW_HISTORY: &str = "show-history";
+pub const SHOW_SETTINGS: &str = "show-settings";
+pub const SHORTCUT_CONFLICT: &str = "shortcut-conflict";
+pub const SETTINGS_CHANGED: &str = "settings-changed";
+// 窗口显示后让搜索框获得焦点（受 focus_search_on_show 控制）
+pub const FOCUS_SEARCH: &str = "focus-search";
+// 由外部设置搜索词，载荷为搜索词字符串
+pub const SET_SEARCH: &str = "set-search";
+
+// 剪切板历史变化
+pub const CLIPBOARD_UPDATED: &str = "clipboard-updated";
+pub const CLIPBOARD_ITEM_ADDED: &str = "clipboard-item-added";
+pub const CLIPBOARD_ITEM_REMOVED: &str = "clipboard-item-removed";
+pub const CLIPBOARD_ITEM_UPDATED: &str = "clipboard-item-updated";
+pub const CLIPBOARD_ITEM_FAVORITED: &str = "clipboard-item-favorited";
+pub const CLIPBOARD_CLEARED: &str = "clipboard-cleared";
+pub const HISTORY_RELOADED: &str = "history-reloaded";
+// 历史记录关闭时的实时剪切板内容，只供预览，没有对应的条目
+pub const CLIPBOARD_PREVIEW: &str = "clipboard-preview";
+// 临时放开大小限制后记录到了超大内容，载荷为条目 id
+pub const OVERSIZE_CAPTURED: &str = "oversize-captured";
+// 连续多次保存失败，历史没有写入磁盘
+pub const STORAGE_SAVE_FAILED: &str = "storage-save-failed";
+
+// 会改变历史条数的事件，托盘提示等需要随之刷新
+pub const HISTORY_CHANGE_EVENTS: [&str; 4] = [
+    CLIPBOARD_ITEM_ADDED,
+    CLIPBOARD_ITEM_REMOVED,
+    CLIPBOARD_CLEARED,
+    HISTORY_RELOADED,
+];
+
+// 事件载荷：删除、收藏等只携带受影响的 id，前端据此局部更新列表而无需重新拉取
+
+// CLIPBOARD_ITEM_ADDED
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemAddedPayload<'a> {
+    pub item: &'a ClipboardItem,
+}
+
+// CLIPBOARD_ITEM_REMOVED
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemRemovedPayload {
+    pub id: u64,
+}
+
+// CLIPBOARD_ITEM_FAVORITED
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemFavoritedPayload {
+    pub id: u64,
+    pub is_favorite: bool,
+}
+
+// CLIPBOARD_PREVIEW：内容未保存，因此不带 id，前端不能按 id 对它执行删除、粘贴等操作
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardPreviewPayload<'a> {
+    pub content: &'a str,
+    pub timestamp: u64,
+}
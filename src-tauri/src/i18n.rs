@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 后端消息使用的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Locale {
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en")]
+    En,
+}
+
+impl Locale {
+    /// 根据系统语言推断，非中文环境统一使用英文
+    pub fn detect() -> Self {
+        match sys_locale::get_locale() {
+            Some(tag) if tag.to_lowercase().starts_with("zh") => Locale::ZhCn,
+            Some(_) => Locale::En,
+            None => Locale::ZhCn,
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::ZhCn => ZH_CN,
+            Locale::En => EN,
+        }
+    }
+}
+
+static CURRENT_LOCALE: AtomicU8 = AtomicU8::new(0);
+
+/// 切换当前语言，启动时和设置变更时调用
+pub fn set_locale(locale: Locale) {
+    let value = match locale {
+        Locale::ZhCn => 0,
+        Locale::En => 1,
+    };
+    CURRENT_LOCALE.store(value, Ordering::Relaxed);
+}
+
+pub fn current_locale() -> Locale {
+    match CURRENT_LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::En,
+        _ => Locale::ZhCn,
+    }
+}
+
+/// 获取当前语言下的消息，缺失时回退到中文
+pub fn t(key: &str) -> &'static str {
+    t_in(current_locale(), key)
+}
+
+/// 获取指定语言下的消息，不读写全局语言设置
+pub fn t_in(locale: Locale, key: &str) -> &'static str {
+    lookup(locale.table(), key)
+        .or_else(|| lookup(ZH_CN, key))
+        .unwrap_or("")
+}
+
+/// 获取消息并按顺序替换其中的 `{}` 占位符
+pub fn tf(key: &str, args: &[&dyn Display]) -> String {
+    tf_in(current_locale(), key, args)
+}
+
+pub fn tf_in(locale: Locale, key: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = t_in(locale, key).split("{}");
+    let mut message = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            message.push_str(&arg.to_string());
+        }
+        message.push_str(part);
+    }
+    message
+}
+
+/// 按数量选择单复数形式并填入数量。只有区分单复数的语言才需要提供 singular 条目，
+/// 其余语言只写 plural 一条即可
+pub fn tf_count(singular: &str, plural: &str, count: i64) -> String {
+    tf_count_in(current_locale(), singular, plural, count)
+}
+
+pub fn tf_count_in(locale: Locale, singular: &str, plural: &str, count: i64) -> String {
+    let key = if count == 1 && lookup(locale.table(), singular).is_some() {
+        singular
+    } else {
+        plural
+    };
+    tf_in(locale, key, &[&count])
+}
+
+fn lookup(table: &'static [(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(entry_key, _)| *entry_key == key)
+        .map(|(_, message)| *message)
+}
+
+const ZH_CN: &[(&str, &str)] = &[
+    // 通用
+    ("app.fallback_name", "应用程序"),
+    ("error.item_not_found", "项目不存在"),
+    ("error.save_settings", "保存设置失败: {}"),
+    // 剪切板
+    ("error.create_clipboard", "创建剪切板上下文失败: {}"),
+    ("error.set_clipboard", "设置剪切板内容失败: {}"),
+    ("error.restore_clipboard", "恢复原剪切板内容失败: {}"),
+    // 历史记录
+    ("error.delete_item", "删除项目失败: {}"),
+    ("error.update_favorite", "更新置顶状态失败: {}"),
+    ("error.clear_history", "清除历史记录失败: {}"),
+    ("error.clear_needs_confirmation", "清除历史需要确认，请先调用 request_clear_history 获取令牌（有效期30秒）"),
+    ("error.save_scratchpad", "保存便签失败: {}"),
+    ("error.max_items_zero", "最大条数必须大于0"),
+    ("error.apply_item_limit", "应用条数限制失败: {}"),
+    ("error.update_first_launch", "更新首次启动状态失败: {}"),
+    // 快捷键
+    ("error.save_shortcut", "保存快捷键失败: {}"),
+    ("error.shortcut_conflict", "快捷键冲突 {}"),
+    ("error.unregister_shortcut", "注销快捷键失败: {}"),
+    ("error.invalid_shortcut", "无效的快捷键: {}"),
+    ("shortcut.conflict_message", "快捷键 {} 已被其他程序占用"),
+    ("shortcut.conflict_suggestion", "请通过系统托盘右键菜单打开设置，修改为其他快捷键组合"),
+    // 窗口与进程
+    ("error.hide_window", "隐藏窗口失败: {}"),
+    ("error.current_exe", "获取可执行文件路径失败: {}"),
+    ("error.spawn_process", "启动新进程失败: {}"),
+    ("error.pin_above_fullscreen", "设置全屏覆盖失败: {}"),
+    ("error.ns_window", "获取NSWindow失败: {}"),
+    // 粘贴
+    ("error.init_keyboard", "初始化键盘输入失败: {}"),
+    ("error.type_text", "键盘输入失败: {}（可在设置中改用快捷键粘贴，速度更快且兼容输入法，但会覆盖剪切板）"),
+    ("error.paste_keystroke", "模拟粘贴快捷键失败: {}（可在设置中改用逐字输入，速度较慢但不会覆盖剪切板）"),
+    // 托盘
+    ("tray.tooltip", "剪切板管理器"),
+    ("tray.tooltip_with_count", "{} — {} 条记录"),
+    ("tray.show", "显示/隐藏"),
+    ("tray.settings", "设置"),
+    ("tray.quit", "退出"),
+    // 本地接口
+    ("api.start_failed", "启动本地接口失败: {}"),
+    ("api.unauthorized", "未授权"),
+    ("api.no_items", "暂无记录"),
+    ("api.read_body_failed", "读取请求内容失败: {}"),
+    ("api.empty_content", "内容不能为空"),
+    ("api.add_failed", "新增记录失败"),
+    ("api.unknown_endpoint", "未知接口"),
+    ("api.history_disabled", "历史记录已关闭"),
+    ("api.capture_filtered", "内容不符合记录规则"),
+    // 权限与系统设置
+    ("permission.unknown_type", "未知的权限类型"),
+    ("permission.request_failed", "请求权限失败: {}"),
+    ("permission.request_handled", "权限请求已处理"),
+    ("permission.accessibility_required", "{} 需要辅助功能权限来监听全局快捷键。请在系统设置中启用。"),
+    ("permission.notification_required", "{} 需要通知权限来显示剪切板操作提示。"),
+    (
+        "permission.accessibility_steps",
+        "需要启用辅助功能权限：\n1. 打开系统偏好设置\n2. 进入「安全性与隐私」\n3. 选择「隐私」标签\n4. 找到「辅助功能」并勾选 {}\n5. 重启应用以使权限生效",
+    ),
+    (
+        "permission.notification_steps",
+        "需要启用通知权限：\n1. 打开系统偏好设置\n2. 进入「通知」\n3. 在左侧找到 {}\n4. 允许发送通知",
+    ),
+    ("settings.unknown_type", "未知的设置类型"),
+    ("settings.opened", "系统设置已打开"),
+    ("settings.open_failed", "打开系统设置失败: {}"),
+    ("settings.notifications_opened", "通知设置已打开"),
+    ("settings.notifications_open_failed", "打开通知设置失败: {}"),
+    ("settings.windows_not_required", "Windows平台无需特殊设置"),
+    ("settings.open_manually", "无法打开系统设置，请手动打开隐私设置"),
+    // 设置校验
+    ("error.invalid_opacity", "窗口不透明度需在 {} 到 {} 之间"),
+    // 内容转换
+    ("error.not_a_url", "该项目不是有效的链接"),
+    // 收藏夹
+    ("error.collection_name_empty", "收藏夹名称不能为空"),
+    ("error.collection_not_found", "收藏夹不存在"),
+    ("error.update_collection", "更新收藏夹失败: {}"),
+    // 追加复制
+    ("error.content_too_large", "内容过大，超过大小上限"),
+    ("error.add_item", "新增记录失败: {}"),
+    // 共享剪切板
+    ("error.read_clipboard", "读取剪切板失败: {}"),
+    // 颜色标记
+    ("error.update_color", "更新颜色标记失败: {}"),
+    // 导出文件
+    ("error.parent_dir_missing", "目标目录不存在: {}"),
+    ("error.save_file", "写入文件失败: {}"),
+    // 默认显示条数
+    ("error.invalid_history_limit", "默认显示条数需在 1 到最大条数 {} 之间"),
+    // 数据自检
+    ("error.verify_storage", "数据文件自检失败: {}"),
+    ("verify.empty_item", "条目 {} 内容为空"),
+    ("verify.duplicate_id", "存在重复的条目 id {}"),
+    ("verify.next_id", "next_id {} 不大于已有的最大 id {}"),
+    ("verify.bad_timestamp", "条目 {} 的时间戳异常: {}"),
+    ("verify.too_many_favorites", "置顶条目数 {} 超过最大条数 {}"),
+    // 删除时清空剪切板
+    ("error.clear_clipboard", "清空剪切板失败: {}"),
+    // 捕获规则
+    ("error.invalid_capture_pattern", "无效的仅记录规则: {}"),
+    // 撤销删除
+    ("error.undo_delete", "撤销删除失败: {}"),
+    // 迁移数据文件
+    ("error.relocate_storage", "迁移数据文件失败: {}"),
+    ("error.primary_selection_unsupported", "当前平台不支持 PRIMARY 选区（仅 X11 可用）"),
+    ("error.read_file", "读取文件失败: {}"),
+    ("error.import_history", "导入历史失败: {}"),
+    ("error.window_not_found", "找不到主窗口"),
+    ("error.show_window", "显示窗口失败: {}"),
+    ("error.reload_storage", "重新加载数据文件失败: {}"),
+    ("error.update_note", "更新备注失败: {}"),
+    ("error.capture_shortcut_conflict", "“立即记录”快捷键与主快捷键相同"),
+    // 相对时间
+    ("time.just_now", "刚刚"),
+    ("time.minutes_ago", "{} 分钟前"),
+    ("time.hours_ago", "{} 小时前"),
+    ("time.yesterday", "昨天 {}"),
+    ("error.invalid_format_data", "格式 {} 的数据不是有效的 base64: {}"),
+    ("error.command_not_allowed", "命令 {} 不在允许列表中"),
+    ("error.run_command", "执行命令 {} 失败: {}"),
+    ("error.command_timeout", "命令 {} 执行超时"),
+    ("error.command_failed", "命令 {} 退出码 {}: {}"),
+    ("error.export_history", "导出历史失败: {}"),
+    ("error.read_raw_storage", "读取数据失败: {}"),
+    ("error.write_raw_storage", "写入数据失败: {}"),
+    ("error.set_session_only", "切换仅会话模式失败: {}"),
+    // 存储与剪切板底层错误
+    ("error.storage_io", "文件读写失败: {}"),
+    ("error.storage_parse", "数据解析失败: {}"),
+    ("error.item_id_not_found", "项目不存在: {}"),
+    ("error.storage_exists", "目标位置已存在数据文件: {}"),
+    ("error.clipboard_op", "剪切板操作失败: {}"),
+    ("error.storage_op", "存储操作失败: {}"),
+    ("error.invalid_pipe_command", "命令须以可执行文件的绝对路径开头: {}"),
+];
+
+const EN: &[(&str, &str)] = &[
+    // 通用
+    ("app.fallback_name", "the application"),
+    ("error.item_not_found", "Item not found"),
+    ("error.save_settings", "Failed to save settings: {}"),
+    // 剪切板
+    ("error.create_clipboard", "Failed to open the clipboard: {}"),
+    ("error.set_clipboard", "Failed to set clipboard content: {}"),
+    ("error.restore_clipboard", "Failed to restore the previous clipboard content: {}"),
+    // 历史记录
+    ("error.delete_item", "Failed to delete item: {}"),
+    ("error.update_favorite", "Failed to update pinned state: {}"),
+    ("error.clear_history", "Failed to clear history: {}"),
+    ("error.clear_needs_confirmation", "Clearing history requires confirmation; call request_clear_history first to get a token (valid for 30 seconds)"),
+    ("error.save_scratchpad", "Failed to save scratchpad: {}"),
+    ("error.max_items_zero", "Maximum item count must be greater than 0"),
+    ("error.apply_item_limit", "Failed to apply item limit: {}"),
+    ("error.update_first_launch", "Failed to update first-launch state: {}"),
+    // 快捷键
+    ("error.save_shortcut", "Failed to save shortcut: {}"),
+    ("error.shortcut_conflict", "Shortcut conflict {}"),
+    ("error.unregister_shortcut", "Failed to unregister shortcut: {}"),
+    ("error.invalid_shortcut", "Invalid shortcut: {}"),
+    ("shortcut.conflict_message", "Shortcut {} is already in use by another program"),
+    ("shortcut.conflict_suggestion", "Open settings from the tray menu and choose a different shortcut"),
+    // 窗口与进程
+    ("error.hide_window", "Failed to hide window: {}"),
+    ("error.current_exe", "Failed to locate the executable: {}"),
+    ("error.spawn_process", "Failed to start new process: {}"),
+    ("error.pin_above_fullscreen", "Failed to show window above fullscreen apps: {}"),
+    ("error.ns_window", "Failed to get NSWindow: {}"),
+    // 粘贴
+    ("error.init_keyboard", "Failed to initialize keyboard input: {}"),
+    ("error.type_text", "Keyboard input failed: {} (switch to keystroke paste in settings: faster and IME-safe, but overwrites the clipboard)"),
+    ("error.paste_keystroke", "Failed to send paste keystroke: {} (switch to typing in settings: slower, but leaves the clipboard untouched)"),
+    // 托盘
+    ("tray.tooltip", "Clipper"),
+    ("tray.tooltip_with_count", "{} — {} items"),
+    ("tray.show", "Show/Hide"),
+    ("tray.settings", "Settings"),
+    ("tray.quit", "Quit"),
+    // 本地接口
+    ("api.start_failed", "Failed to start local API: {}"),
+    ("api.unauthorized", "Unauthorized"),
+    ("api.no_items", "No items yet"),
+    ("api.read_body_failed", "Failed to read request body: {}"),
+    ("api.empty_content", "Content must not be empty"),
+    ("api.add_failed", "Failed to add item"),
+    ("api.unknown_endpoint", "Unknown endpoint"),
+    ("api.history_disabled", "History is turned off"),
+    ("api.capture_filtered", "Content does not match the capture rules"),
+    // 权限与系统设置
+    ("permission.unknown_type", "Unknown permission type"),
+    ("permission.request_failed", "Permission request failed: {}"),
+    ("permission.request_handled", "Permission request handled"),
+    ("permission.accessibility_required", "{} needs Accessibility permission to listen for global shortcuts. Please enable it in System Settings."),
+    ("permission.notification_required", "{} needs notification permission to show clipboard notifications."),
+    (
+        "permission.accessibility_steps",
+        "Accessibility permission is required:\n1. Open System Settings\n2. Go to \"Security & Privacy\"\n3. Select the \"Privacy\" tab\n4. Find \"Accessibility\" and check {}\n5. Restart the app for the permission to take effect",
+    ),
+    (
+        "permission.notification_steps",
+        "Notification permission is required:\n1. Open System Settings\n2. Go to \"Notifications\"\n3. Find {} in the list\n4. Allow notifications",
+    ),
+    ("settings.unknown_type", "Unknown settings type"),
+    ("settings.opened", "System settings opened"),
+    ("settings.open_failed", "Failed to open system settings: {}"),
+    ("settings.notifications_opened", "Notification settings opened"),
+    ("settings.notifications_open_failed", "Failed to open notification settings: {}"),
+    ("settings.windows_not_required", "No special settings are needed on Windows"),
+    ("settings.open_manually", "Could not open system settings; please open the privacy settings manually"),
+    // 设置校验
+    ("error.invalid_opacity", "Window opacity must be between {} and {}"),
+    // 内容转换
+    ("error.not_a_url", "This item is not a valid link"),
+    // 收藏夹
+    ("error.collection_name_empty", "Collection name must not be empty"),
+    ("error.collection_not_found", "Collection not found"),
+    ("error.update_collection", "Failed to update collection: {}"),
+    // 追加复制
+    ("error.content_too_large", "Content is too large (over the size limit)"),
+    ("error.add_item", "Failed to add item: {}"),
+    // 共享剪切板
+    ("error.read_clipboard", "Failed to read clipboard: {}"),
+    // 颜色标记
+    ("error.update_color", "Failed to update color label: {}"),
+    // 导出文件
+    ("error.parent_dir_missing", "Target directory does not exist: {}"),
+    ("error.save_file", "Failed to write file: {}"),
+    // 默认显示条数
+    ("error.invalid_history_limit", "Default history size must be between 1 and the maximum item count ({})"),
+    // 数据自检
+    ("error.verify_storage", "Storage check failed: {}"),
+    ("verify.empty_item", "Item {} has empty content"),
+    ("verify.duplicate_id", "Duplicate item id {}"),
+    ("verify.next_id", "next_id {} is not greater than the largest existing id {}"),
+    ("verify.bad_timestamp", "Item {} has an invalid timestamp: {}"),
+    ("verify.too_many_favorites", "{} pinned items exceed the maximum item count of {}"),
+    // 删除时清空剪切板
+    ("error.clear_clipboard", "Failed to clear clipboard: {}"),
+    // 捕获规则
+    ("error.invalid_capture_pattern", "Invalid capture-only pattern: {}"),
+    // 撤销删除
+    ("error.undo_delete", "Failed to undo delete: {}"),
+    // 迁移数据文件
+    ("error.relocate_storage", "Failed to relocate data file: {}"),
+    ("error.primary_selection_unsupported", "Primary selection is not supported on this platform (X11 only)"),
+    ("error.read_file", "Failed to read file: {}"),
+    ("error.import_history", "Failed to import history: {}"),
+    ("error.window_not_found", "Main window not found"),
+    ("error.show_window", "Failed to show window: {}"),
+    ("error.reload_storage", "Failed to reload data file: {}"),
+    ("error.update_note", "Failed to update note: {}"),
+    ("error.capture_shortcut_conflict", "The capture shortcut must differ from the main shortcut"),
+    // 相对时间
+    ("time.just_now", "just now"),
+    ("time.minute_ago", "{} minute ago"),
+    ("time.minutes_ago", "{} minutes ago"),
+    ("time.hour_ago", "{} hour ago"),
+    ("time.hours_ago", "{} hours ago"),
+    ("time.yesterday", "yesterday {}"),
+    ("error.invalid_format_data", "Data for format {} is not valid base64: {}"),
+    ("error.command_not_allowed", "Command {} is not in the allowlist"),
+    ("error.run_command", "Failed to run command {}: {}"),
+    ("error.command_timeout", "Command {} timed out"),
+    ("error.command_failed", "Command {} exited with {}: {}"),
+    ("error.export_history", "Failed to export history: {}"),
+    ("error.read_raw_storage", "Failed to read data: {}"),
+    ("error.write_raw_storage", "Failed to write data: {}"),
+    ("error.set_session_only", "Failed to switch session-only mode: {}"),
+    // 存储与剪切板底层错误
+    ("error.storage_io", "File read/write failed: {}"),
+    ("error.storage_parse", "Failed to parse data: {}"),
+    ("error.item_id_not_found", "Item not found: {}"),
+    ("error.storage_exists", "A data file already exists at the target location: {}"),
+    ("error.clipboard_op", "Clipboard operation failed: {}"),
+    ("error.storage_op", "Storage operation failed: {}"),
+    ("error.invalid_pipe_command", "Commands must start with the absolute path of an executable: {}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_uses_singular_only_where_the_locale_has_one() {
+        assert_eq!(tf_count_in(Locale::En, "time.minute_ago", "time.minutes_ago", 1), "1 minute ago");
+        assert_eq!(tf_count_in(Locale::En, "time.minute_ago", "time.minutes_ago", 2), "2 minutes ago");
+        assert_eq!(tf_count_in(Locale::ZhCn, "time.minute_ago", "time.minutes_ago", 1), "1 分钟前");
+        assert_eq!(tf_count_in(Locale::ZhCn, "time.hour_ago", "time.hours_ago", 3), "3 小时前");
+    }
+}
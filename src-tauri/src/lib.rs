@@ -4,13 +4,24 @@ mod storage;
 mod clipboard;
 mod platform;
 mod platform_commands;
-
-use std::sync::{Arc, Mutex};
+mod paste;
+mod local_api;
+mod events;
+mod i18n;
+mod transform;
+mod clock;
+mod settings_schema;
+mod pipe;
+mod error;
+
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Instant;
 use tauri::PhysicalPosition as DpiPhysicalPosition;
 use tauri::image::Image;
 use tauri::{AppHandle, Emitter, Listener, Manager, Position, State};
-use storage::{ClipboardItem, SharedStorage, SimpleStorage};
+use clipboard::SharedClipboard;
+use error::CommandError;
+use storage::{lock_storage, read_storage, ClipboardItem, Collection, HistoryOrder, ItemColor, PasteMethod, SharedStorage, SimpleStorage, SortMode, WindowAnchor, WindowMode};
 use platform::{get_platform_adapter, Permission};
 use serde_json::json;
 use std::collections::HashSet;
@@ -42,6 +53,15 @@ impl ShortcutManager {
 
     // 注册快捷键
     pub fn register_shortcut(&self, shortcut: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.register_shortcut_with(shortcut, handle_app_toggle)
+    }
+
+    // 注册快捷键，按下时执行 action
+    pub fn register_shortcut_with(
+        &self,
+        shortcut: &str,
+        action: fn(&AppHandle),
+    ) -> Result<(), Box<dyn std::error::Error>> {
         use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
         // 检查是否已经注册
@@ -68,7 +88,7 @@ impl ShortcutManager {
                 // 只处理按键按下事件，忽略释放事件
                 if event.state == ShortcutState::Pressed {
                     dev_log!("快捷键被触发: {:?}, 状态: {:?}", shortcut_event, event);
-                    handle_app_toggle(app);
+                    action(app);
                 }
             }
         )?;
@@ -100,12 +120,35 @@ impl ShortcutManager {
                     registered.insert(shortcut.to_string());
                     Ok(())
                 } else {
-                    Err(format!("快捷键冲突 {}", e).into())
+                    Err(i18n::tf("error.shortcut_conflict", &[&e]).into())
                 }
             }
         }
     }
 
+    // 探测快捷键是否可用：不安装处理器，只尝试注册后立即注销
+    pub fn test_shortcut_availability(&self, shortcut: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+        shortcut.parse::<Shortcut>()?;
+
+        let global_shortcut = self.app_handle.global_shortcut();
+        // 本应用已注册的快捷键视为可用
+        if global_shortcut.is_registered(shortcut) {
+            return Ok(true);
+        }
+
+        let register_result = global_shortcut.register(shortcut);
+        // 注册接口可能返回错误但实际已注册成功，以实际状态为准
+        let available = register_result.is_ok() || global_shortcut.is_registered(shortcut);
+        if available {
+            let _ = global_shortcut.unregister(shortcut);
+        } else {
+            dev_log!("快捷键已被占用: {}", shortcut);
+        }
+        Ok(available)
+    }
+
     pub fn unregister_shortcut(&self, shortcut: &str) -> Result<(), Box<dyn std::error::Error>> {
         use tauri_plugin_global_shortcut::GlobalShortcutExt;
 
@@ -118,7 +161,7 @@ impl ShortcutManager {
             }
             Err(e) => {
                 eprintln!("注销快捷键失败: {} - {}", shortcut, e);
-                Err(format!("注销快捷键失败: {}", e).into())
+                Err(i18n::tf("error.unregister_shortcut", &[&e]).into())
             }
         }
     }
@@ -144,9 +187,17 @@ impl ShortcutManager {
     }
 }
 
+// 清空历史确认令牌的有效期
+const CLEAR_TOKEN_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
 struct UiState {
     disable_hotkey_toggle: Arc<Mutex<bool>>,
     last_window_move: Arc<Mutex<Option<Instant>>>,
+    // 在此时间之前失焦不自动隐藏窗口，用于粘贴后保持窗口打开
+    suppress_blur_hide_until: Arc<Mutex<Option<Instant>>>,
+    // 呼出窗口前的前台窗口，即粘贴目标
+    paste_target: Arc<Mutex<Option<u64>>>,
+    clear_token: Arc<Mutex<Option<(String, Instant)>>>,
 }
 
 impl Default for UiState {
@@ -154,49 +205,169 @@ impl Default for UiState {
         Self {
             disable_hotkey_toggle: Arc::new(Mutex::new(false)),
             last_window_move: Arc::new(Mutex::new(None)),
+            suppress_blur_hide_until: Arc::new(Mutex::new(None)),
+            paste_target: Arc::new(Mutex::new(None)),
+            clear_token: Arc::new(Mutex::new(None)),
         }
     }
 }
 
-fn position_window_near_cursor(window: &tauri::WebviewWindow, cursor: DpiPhysicalPosition<f64>) {
+// 按目标显示器的缩放比例换算窗口尺寸，保持逻辑尺寸不变，返回调整后的外框物理尺寸
+fn scale_window_for_monitor(
+    window: &tauri::WebviewWindow,
+    monitor: Option<&tauri::Monitor>,
+) -> tauri::Result<tauri::PhysicalSize<u32>> {
+    let outer = window.outer_size()?;
+    let monitor = match monitor {
+        Some(monitor) => monitor,
+        None => return Ok(outer),
+    };
+
+    let current_scale = window.scale_factor()?;
+    let target_scale = monitor.scale_factor();
+    if (current_scale - target_scale).abs() < f64::EPSILON {
+        return Ok(outer);
+    }
+
+    let inner = window.inner_size()?;
+    let target = inner
+        .to_logical::<f64>(current_scale)
+        .to_physical::<u32>(target_scale);
+    window.set_size(tauri::Size::Physical(target))?;
+    dev_log!(
+        "显示器缩放比例 {} -> {}，窗口尺寸调整为 {}x{}",
+        current_scale,
+        target_scale,
+        target.width,
+        target.height
+    );
+
+    // 边框部分不随内容缩放，沿用原来的差值
+    Ok(tauri::PhysicalSize::new(
+        outer.width.saturating_sub(inner.width) + target.width,
+        outer.height.saturating_sub(inner.height) + target.height,
+    ))
+}
+
+// 窗口左上角允许的范围 (min_x, min_y, max_x, max_y)，与显示器边缘保留间距；
+// 拿不到显示器信息时以 point 为中心限制在窗口尺寸以内
+fn placement_bounds(
+    monitor: Option<&tauri::Monitor>,
+    window_size: tauri::PhysicalSize<u32>,
+    point: Option<(f64, f64)>,
+) -> (f64, f64, f64, f64) {
     const EDGE_MARGIN: f64 = 8.0;
-    const CURSOR_GAP: f64 = 18.0;
 
-    let window_size = match window.outer_size() {
-        Ok(size) => size,
-        Err(err) => {
-            eprintln!("无法获取窗口尺寸: {}", err);
-            return;
+    let (min_x, min_y, mut max_x, mut max_y) = match (monitor, point) {
+        (Some(monitor), _) => {
+            let origin = monitor.position();
+            let size = monitor.size();
+            (
+                origin.x as f64 + EDGE_MARGIN,
+                origin.y as f64 + EDGE_MARGIN,
+                origin.x as f64 + size.width as f64 - window_size.width as f64 - EDGE_MARGIN,
+                origin.y as f64 + size.height as f64 - window_size.height as f64 - EDGE_MARGIN,
+            )
         }
+        (None, Some((x, y))) => (
+            x - window_size.width as f64,
+            y - window_size.height as f64,
+            x,
+            y,
+        ),
+        (None, None) => (0.0, 0.0, 0.0, 0.0),
     };
 
-    let mut min_x = cursor.x - window_size.width as f64;
-    let mut min_y = cursor.y - window_size.height as f64;
-    let mut max_x = cursor.x;
-    let mut max_y = cursor.y;
-
-    if let Ok(Some(monitor)) = window.current_monitor() {
-        let origin = monitor.position();
-        let size = monitor.size();
-        min_x = origin.x as f64 + EDGE_MARGIN;
-        min_y = origin.y as f64 + EDGE_MARGIN;
-        max_x = origin.x as f64 + size.width as f64 - window_size.width as f64 - EDGE_MARGIN;
-        max_y = origin.y as f64 + size.height as f64 - window_size.height as f64 - EDGE_MARGIN;
-    }
-
     if max_x < min_x {
         max_x = min_x;
     }
     if max_y < min_y {
         max_y = min_y;
     }
+    (min_x, min_y, max_x, max_y)
+}
 
-    let mut target_x = cursor.x - (window_size.width as f64 / 2.0);
-    let mut target_y = cursor.y + CURSOR_GAP;
+fn position_window(
+    window: &tauri::WebviewWindow,
+    anchor: WindowAnchor,
+    cursor: Option<DpiPhysicalPosition<f64>>,
+    tray_rect: Option<tauri::Rect>,
+) {
+    const CURSOR_GAP: f64 = 18.0;
+    const TRAY_GAP: f64 = 4.0;
+
+    // 托盘图标的物理坐标与尺寸 (x, y, 宽, 高)
+    let tray = match (anchor, tray_rect) {
+        (WindowAnchor::Tray, Some(rect)) => {
+            let scale = window.scale_factor().unwrap_or(1.0);
+            let position = rect.position.to_physical::<f64>(scale);
+            let size = rect.size.to_physical::<f64>(scale);
+            Some((position.x, position.y, size.width, size.height))
+        }
+        _ => None,
+    };
 
-    if target_y > max_y {
-        target_y = cursor.y - window_size.height as f64 - CURSOR_GAP;
-    }
+    // 目标显示器是托盘或光标所在的显示器，不一定是窗口当前所在的显示器
+    let anchor_point = match (anchor, tray, cursor) {
+        (_, Some((x, y, width, height)), _) => Some((x + width / 2.0, y + height / 2.0)),
+        (WindowAnchor::Cursor | WindowAnchor::Tray, None, Some(cursor)) => Some((cursor.x, cursor.y)),
+        _ => None,
+    };
+    let monitor = anchor_point
+        .and_then(|(x, y)| window.monitor_from_point(x, y).ok().flatten())
+        .or_else(|| window.current_monitor().ok().flatten());
+
+    let window_size = match scale_window_for_monitor(window, monitor.as_ref()) {
+        Ok(size) => size,
+        Err(err) => {
+            eprintln!("无法获取窗口尺寸: {}", err);
+            return;
+        }
+    };
+
+    // 非光标定位且拿不到显示器信息时，只能居中；托盘位置未知时退回光标定位
+    let cursor = match (anchor, cursor) {
+        (WindowAnchor::Cursor, Some(cursor)) => Some(cursor),
+        (WindowAnchor::Tray, Some(cursor)) if tray.is_none() => Some(cursor),
+        _ if monitor.is_none() => {
+            let _ = window.center();
+            return;
+        }
+        _ => None,
+    };
+
+    let (min_x, min_y, max_x, max_y) = placement_bounds(
+        monitor.as_ref(),
+        window_size,
+        cursor.map(|cursor| (cursor.x, cursor.y)),
+    );
+
+    let (mut target_x, mut target_y) = match (anchor, cursor, tray) {
+        (_, None, Some((x, y, width, height))) => {
+            let target_x = x + width / 2.0 - window_size.width as f64 / 2.0;
+            // 托盘在屏幕上半部分时窗口放在图标下方，否则放在上方
+            let monitor_mid_y = (min_y + max_y + window_size.height as f64) / 2.0;
+            let target_y = if y + height / 2.0 < monitor_mid_y {
+                y + height + TRAY_GAP
+            } else {
+                y - window_size.height as f64 - TRAY_GAP
+            };
+            (target_x, target_y)
+        }
+        (_, Some(cursor), _) => {
+            let target_x = cursor.x - (window_size.width as f64 / 2.0);
+            let mut target_y = cursor.y + CURSOR_GAP;
+            if target_y > max_y {
+                target_y = cursor.y - window_size.height as f64 - CURSOR_GAP;
+            }
+            (target_x, target_y)
+        }
+        (WindowAnchor::TopRight, None, None) => (max_x, min_y),
+        (WindowAnchor::BottomRight, None, None) => (max_x, max_y),
+        (WindowAnchor::Center | WindowAnchor::Cursor | WindowAnchor::Tray, None, None) => {
+            ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0)
+        }
+    };
 
     target_x = target_x.clamp(min_x, max_x);
     target_y = target_y.clamp(min_y, max_y);
@@ -211,6 +382,61 @@ fn position_window_near_cursor(window: &tauri::WebviewWindow, cursor: DpiPhysica
     }
 }
 
+// 读取用户设置的窗口管理方式
+fn current_window_mode(app: &tauri::AppHandle) -> WindowMode {
+    match app.try_state::<SharedStorage>() {
+        Some(storage) => read_storage(&storage).data.settings.window_mode,
+        None => WindowMode::default(),
+    }
+}
+
+// 读取用户设置的窗口定位方式
+fn current_window_anchor(app: &tauri::AppHandle) -> WindowAnchor {
+    match app.try_state::<SharedStorage>() {
+        Some(storage) => {
+            let storage = read_storage(&storage);
+            storage.data.settings.window_anchor
+        }
+        None => WindowAnchor::default(),
+    }
+}
+
+// 读取托盘图标在屏幕上的位置，部分平台（如 Linux）无法提供
+fn tray_icon_rect(app: &tauri::AppHandle) -> Option<tauri::Rect> {
+    let tray_state = app.try_state::<TrayState>()?;
+    match tray_state.tray.rect() {
+        Ok(rect) => rect,
+        Err(err) => {
+            eprintln!("无法获取托盘图标位置: {}", err);
+            None
+        }
+    }
+}
+
+// 托盘图标句柄，用于运行时更新提示文字
+struct TrayState {
+    tray: tauri::tray::TrayIcon,
+}
+
+// 根据当前记录条数刷新托盘提示
+fn update_tray_tooltip(app: &AppHandle) {
+    let Some(tray_state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    let Some(storage) = app.try_state::<SharedStorage>() else {
+        return;
+    };
+
+    let count = {
+        let storage = read_storage(&storage);
+        storage.data.items.len()
+    };
+    let tooltip = i18n::tf("tray.tooltip_with_count", &[&i18n::t("tray.tooltip"), &count]);
+    if let Err(e) = tray_state.tray.set_tooltip(Some(tooltip)) {
+        eprintln!("更新托盘提示失败: {}", e);
+    }
+}
+
 fn build_tray_icon_image() -> Image<'static> {
     const SIZE: usize = 32;
     const BYTES_PER_PIXEL: usize = 4;
@@ -286,6 +512,108 @@ fn build_tray_icon_image() -> Image<'static> {
 }
 
 
+// 空闲维护任务的句柄，退出时取消
+struct MaintenanceTask(Mutex<Option<tauri::async_runtime::JoinHandle<()>>>);
+
+// 检查是否需要空闲维护的间隔
+const MAINTENANCE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// 启动空闲维护任务：一段时间没有新捕获后再执行条数与保留天数清理，避免拖慢捕获
+fn spawn_maintenance_scheduler(app: AppHandle) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(MAINTENANCE_CHECK_INTERVAL).await;
+
+            let storage = app.state::<SharedStorage>();
+            let changed = {
+                let mut storage = lock_storage(&storage);
+                let idle = std::time::Duration::from_secs(storage.data.settings.maintenance_idle_secs);
+                if !storage.maintenance_due(idle) {
+                    continue;
+                }
+                match storage.run_maintenance() {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        eprintln!("空闲维护失败: {}", e);
+                        false
+                    }
+                }
+            };
+
+            if changed {
+                dev_log!("空闲维护已清理过期或超出上限的条目");
+                let _ = app.emit(events::HISTORY_RELOADED, ());
+            }
+        }
+    })
+}
+
+// 退出或重启前把尚未落盘的修改写入磁盘
+fn flush_storage(app: &AppHandle) {
+    if let Some(storage) = app.try_state::<SharedStorage>() {
+        if let Err(e) = read_storage(&storage).flush() {
+            eprintln!("退出前保存剪切板数据失败: {}", e);
+        }
+    }
+}
+
+// 显示窗口前记录前台窗口作为粘贴目标，之后焦点切回它时不自动隐藏
+fn remember_paste_target(app: &AppHandle) {
+    if let Ok(mut target) = app.state::<UiState>().paste_target.lock() {
+        *target = get_platform_adapter().foreground_window();
+    }
+}
+
+// 窗口显示后按设置通知前端聚焦搜索框
+fn request_search_focus(app: &AppHandle) {
+    let enabled = read_storage(&app.state::<SharedStorage>())
+        .data
+        .settings
+        .focus_search_on_show;
+    if enabled {
+        let _ = app.emit(events::FOCUS_SEARCH, ());
+    }
+}
+
+// 切换到历史列表页面并按设置的锚点显示主窗口，热键与托盘共用
+fn show_window_near_cursor(app: &tauri::AppHandle) {
+    // 在切换页面前记录光标位置，避免等待期间光标移动
+    let cursor_position = app
+        .cursor_position()
+        .ok()
+        .map(|pos| (pos.x, pos.y));
+    let anchor = current_window_anchor(app);
+    remember_paste_target(app);
+    let tray_rect = if anchor == WindowAnchor::Tray {
+        tray_icon_rect(app)
+    } else {
+        None
+    };
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = app_handle.emit(events::SHOW_HISTORY, ());
+        dev_log!("已发送show-history事件");
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(30)).await;
+
+        if let Some(window) = app_handle.get_webview_window("main") {
+            position_window(
+                &window,
+                anchor,
+                cursor_position.map(|(x, y)| DpiPhysicalPosition::new(x, y)),
+                tray_rect,
+            );
+            if !window.is_visible().unwrap_or(false) {
+                let _ = window.show();
+            }
+            let _ = window.set_focus();
+            request_search_focus(&app_handle);
+            dev_log!("窗口已显示并聚焦（历史列表页面）");
+        }
+    });
+}
+
 // 处理应用切换显示/隐藏
 fn handle_app_toggle(app: &tauri::AppHandle) {
     if let Some(ui_state) = app.try_state::<UiState>() {
@@ -297,11 +625,6 @@ fn handle_app_toggle(app: &tauri::AppHandle) {
         }
     }
 
-    let cursor_position = app
-        .cursor_position()
-        .ok()
-        .map(|pos| (pos.x, pos.y));
-
     if let Some(window) = app.get_webview_window("main") {
         match window.is_visible() {
             Ok(true) => {
@@ -310,34 +633,13 @@ fn handle_app_toggle(app: &tauri::AppHandle) {
             }
             Ok(false) => {
                 dev_log!("窗口不可见，显示窗口");
-
-                let app_handle = app.clone();
-                let cursor_position = cursor_position;
-                tauri::async_runtime::spawn(async move {
-                    let _ = app_handle.emit("show-history", ());
-                    dev_log!("已发送show-history事件");
-
-                    tokio::time::sleep(tokio::time::Duration::from_millis(30)).await;
-
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        if let Some((x, y)) = cursor_position {
-                            position_window_near_cursor(
-                                &window,
-                                DpiPhysicalPosition::new(x, y),
-                            );
-                        }
-                        if !window.is_visible().unwrap_or(false) {
-                            let _ = window.show();
-                        }
-                        let _ = window.set_focus();
-                        dev_log!("窗口已显示并聚焦（历史列表页面）");
-                    }
-                });
+                show_window_near_cursor(app);
             }
             Err(_) => {
                 dev_log!("无法获取窗口状态，显示窗口");
                 let _ = window.show();
                 let _ = window.set_focus();
+                request_search_focus(app);
             }
         }
     } else {
@@ -350,82 +652,699 @@ fn handle_app_toggle(app: &tauri::AppHandle) {
 async fn get_clipboard_history(
     storage: State<'_, SharedStorage>,
     limit: Option<usize>,
+    order: Option<HistoryOrder>,
 ) -> Result<Vec<ClipboardItem>, String> {
-    let storage = storage.lock().map_err(|e| e.to_string())?;
-    let limit = limit.unwrap_or(100);
-    Ok(storage.get_history(limit).to_vec())
+    let storage = read_storage(&storage);
+    let limit = limit.unwrap_or(storage.data.settings.default_history_limit);
+    Ok(storage.get_history(limit, order.unwrap_or_default()))
+}
+
+// 按设置中的排序方式分页获取历史
+#[tauri::command]
+async fn get_sorted_history(
+    storage: State<'_, SharedStorage>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<ClipboardItem>, String> {
+    let storage = read_storage(&storage);
+    let limit = limit.unwrap_or(storage.data.settings.default_history_limit);
+    Ok(storage.get_sorted_history(limit, offset.unwrap_or(0)))
+}
+
+// 修改列表排序方式并保存，下次打开沿用
+#[tauri::command]
+async fn set_sort_mode(
+    app: AppHandle,
+    sort_mode: SortMode,
+    storage: State<'_, SharedStorage>,
+) -> Result<(), String> {
+    let mut storage = lock_storage(&storage);
+    storage.data.settings.sort_mode = sort_mode;
+    storage.mark_dirty();
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
+    Ok(())
+}
+
+// 获取列表渲染用的轻量数据，完整内容按需通过 id 获取
+#[tauri::command]
+async fn get_history_summaries(
+    storage: State<'_, SharedStorage>,
+    limit: Option<usize>,
+    order: Option<HistoryOrder>,
+) -> Result<Vec<storage::ItemSummary>, String> {
+    let storage = read_storage(&storage);
+    let limit = limit.unwrap_or(storage.data.settings.default_history_limit);
+    Ok(storage.get_history_summaries(limit, order.unwrap_or_default()))
+}
+
+// 按 id 获取完整条目
+#[tauri::command]
+async fn get_item(id: u64, storage: State<'_, SharedStorage>) -> Result<ClipboardItem, String> {
+    let storage = read_storage(&storage);
+    storage
+        .get_item_by_id(id)
+        .cloned()
+        .ok_or_else(|| i18n::t("error.item_not_found").to_string())
+}
+
+// 批量获取条目，按请求顺序返回并跳过不存在的 id，供收到只含 id 的事件后局部刷新列表
+#[tauri::command]
+async fn get_items_by_ids(
+    ids: Vec<u64>,
+    storage: State<'_, SharedStorage>,
+) -> Result<Vec<ClipboardItem>, String> {
+    Ok(read_storage(&storage).get_items_by_ids(&ids))
 }
 
 #[tauri::command]
 async fn get_all_clipboard_items(
     storage: State<'_, SharedStorage>,
-) -> Result<Vec<ClipboardItem>, String> {
-    let storage = storage.lock().map_err(|e| e.to_string())?;
-    Ok(storage.get_all_items())
+    order: Option<HistoryOrder>,
+) -> Result<Vec<ClipboardItem>, String> {
+    let storage = read_storage(&storage);
+    Ok(storage.get_all_items(order.unwrap_or_default()))
+}
+
+#[tauri::command]
+async fn search_clipboard_items(
+    storage: State<'_, SharedStorage>,
+    query: String,
+) -> Result<Vec<ClipboardItem>, String> {
+    let storage = read_storage(&storage);
+    let items = storage.search_items(&query);
+    Ok(items)
+}
+
+// 只返回搜索命中条数，供输入过程中显示结果数
+#[tauri::command]
+async fn count_search_matches(
+    storage: State<'_, SharedStorage>,
+    query: String,
+) -> Result<usize, String> {
+    Ok(read_storage(&storage).count_search_matches(&query))
+}
+
+#[tauri::command]
+async fn get_item_stats(
+    id: u64,
+    storage: State<'_, SharedStorage>,
+) -> Result<storage::ItemStats, String> {
+    let storage = read_storage(&storage);
+    storage
+        .get_item_by_id(id)
+        .map(|item| storage::ItemStats::from_content(&item.content))
+        .ok_or_else(|| i18n::t("error.item_not_found").to_string())
+}
+
+// 写入剪切板，随后由监控照常记录到历史；仅需临时放入剪切板、不想留下记录时用 set_clipboard_silent
+#[tauri::command]
+async fn copy_to_clipboard(
+    content: String,
+    transforms: Option<Vec<transform::TextTransform>>,
+    clipboard: State<'_, SharedClipboard>,
+) -> Result<(), String> {
+    let content = transform::apply_text_transforms(&content, &transforms.unwrap_or_default());
+    clipboard.set_text(&content)?;
+
+    dev_log!("内容已复制到剪切板");
+    Ok(())
+}
+
+// 写入剪切板但不记录到历史，也不会在之后恢复原内容，适合“复制链接”之类的界面按钮
+#[tauri::command]
+async fn set_clipboard_silent(
+    content: String,
+    clipboard: State<'_, SharedClipboard>,
+) -> Result<(), String> {
+    clipboard.set_text_silent(&content)?;
+    dev_log!("已静默写入剪切板");
+    Ok(())
+}
+
+// 将内容追加到当前剪切板文本之后，并把合并结果记录为新条目
+#[tauri::command]
+async fn append_to_clipboard(
+    content: String,
+    separator: Option<String>,
+    app: AppHandle,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<ClipboardItem, CommandError> {
+    // 剪切板为空或不是文本时直接以新内容开头
+    let current = clipboard.get_text().ok().flatten().unwrap_or_default();
+
+    let combined = if current.is_empty() {
+        content
+    } else {
+        let separator = separator.unwrap_or_else(|| "\n".to_string());
+        format!("{}{}{}", current, separator, content)
+    };
+
+    if combined.len() > read_storage(&storage).data.settings.limit_for(storage::CaptureKind::Text) {
+        return Err(CommandError::new("too_large", i18n::t("error.content_too_large")));
+    }
+
+    clipboard.set_text(&combined)?;
+
+    let mut storage = lock_storage(&storage);
+    let item_id = storage
+        .add_item(combined)
+        .map_err(|e| CommandError::storage("error.add_item", e))?;
+    let item = storage
+        .get_item_by_id(item_id)
+        .cloned()
+        .ok_or_else(CommandError::not_found)?;
+    let _ = app.emit(events::CLIPBOARD_ITEM_ADDED, events::ItemAddedPayload { item: &item });
+
+    dev_log!("内容已追加到剪切板");
+    Ok(item)
+}
+
+// 按指定转换复制历史项目，例如将链接复制为 Markdown 格式
+#[tauri::command]
+async fn copy_item_transformed(
+    id: u64,
+    transform: transform::CopyTransform,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<String, String> {
+    let (content, fetch_titles) = {
+        let storage = read_storage(&storage);
+        let item = storage
+            .get_item_by_id(id)
+            .ok_or_else(|| i18n::t("error.item_not_found").to_string())?;
+        (item.content.clone(), storage.data.settings.fetch_link_titles)
+    };
+
+    let text = transform::apply_copy_transform(&content, transform, fetch_titles).await?;
+
+    clipboard.set_text(&text)?;
+
+    dev_log!("已按转换方式复制到剪切板: {:?}", transform);
+    Ok(text)
+}
+
+// 复制条目文本及其记录的自定义格式；formats 中的同名格式覆盖记录的数据
+#[tauri::command]
+async fn copy_item_with_formats(
+    id: u64,
+    formats: Option<Vec<storage::ExtraFormat>>,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<(), String> {
+    use base64::Engine;
+
+    let (content, mut merged) = {
+        let storage = read_storage(&storage);
+        let item = storage
+            .get_item_by_id(id)
+            .ok_or_else(|| i18n::t("error.item_not_found").to_string())?;
+        (item.content.clone(), item.extra_formats.clone())
+    };
+    for format in formats.unwrap_or_default() {
+        merged.retain(|existing| existing.mime != format.mime);
+        merged.push(format);
+    }
+
+    let decoded = merged
+        .into_iter()
+        .map(|format| {
+            base64::engine::general_purpose::STANDARD
+                .decode(&format.base64)
+                .map(|data| (format.mime.clone(), data))
+                .map_err(|e| i18n::tf("error.invalid_format_data", &[&format.mime, &e]))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if decoded.is_empty() {
+        clipboard.set_text(&content)?;
+    } else {
+        clipboard.set_text_with_formats(&content, &decoded)?;
+    }
+    dev_log!("已复制条目及 {} 种自定义格式", decoded.len());
+    Ok(())
+}
+
+// 把条目内容写入允许列表中外部命令的标准输入，便于与 xclip、pbcopy 等工具配合。
+// 允许列表默认为空，即默认不允许调用任何命令，需用户在设置中显式添加；
+// 条目为可执行文件的绝对路径加固定参数，command 与 args 须与某一条目完全一致，
+// 内容超过文本大小上限时拒绝
+#[tauri::command]
+async fn pipe_item_to_command(
+    id: u64,
+    command: String,
+    args: Vec<String>,
+    storage: State<'_, SharedStorage>,
+) -> Result<(), String> {
+    let (allowed, content) = {
+        let storage = read_storage(&storage);
+        let allowed = pipe::resolve(&storage.data.settings.pipe_command_allowlist, &command, &args)?;
+        let content = storage
+            .get_item_by_id(id)
+            .map(|item| item.content.clone())
+            .ok_or_else(|| i18n::t("error.item_not_found").to_string())?;
+        pipe::check_size(&content, storage.data.settings.limit_for(storage::CaptureKind::Text))?;
+        (allowed, content)
+    };
+
+    pipe::run(&allowed, &content, pipe::PIPE_COMMAND_TIMEOUT).await?;
+    dev_log!("已将条目 {} 的内容写入命令 {}", id, command);
+    Ok(())
+}
+
+// 把当前剪切板内容暂存到历史（已存在则不重复记录），再把选中条目写入剪切板。
+// 返回暂存内容对应的条目 id，前端可据此“换回”；剪切板为空或不是文本时返回 None
+#[tauri::command]
+async fn swap_clipboard_with_item(
+    id: u64,
+    app: AppHandle,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<Option<u64>, CommandError> {
+    let content = read_storage(&storage)
+        .get_item_by_id(id)
+        .map(|item| item.content.clone())
+        .ok_or_else(CommandError::not_found)?;
+
+    // 读写剪切板可能带退避重试，只在查找和暂存时持有写锁，避免阻塞其他读取
+    let current = clipboard.get_text().ok().flatten().filter(|text| !text.trim().is_empty());
+    let stashed_id = match current {
+        Some(current) => {
+            let mut storage = lock_storage(&storage);
+            match storage.find_by_content(&current).filter(|item| !item.is_scratchpad) {
+                Some(existing) => Some(existing.id),
+                None => {
+                    let stashed_id = storage
+                        .add_item(current)
+                        .map_err(|e| CommandError::storage("error.add_item", e))?;
+                    if let Some(item) = storage.get_item_by_id(stashed_id) {
+                        let _ = app.emit(events::CLIPBOARD_ITEM_ADDED, events::ItemAddedPayload { item });
+                    }
+                    Some(stashed_id)
+                }
+            }
+        }
+        None => None,
+    };
+
+    clipboard.set_text(&content)?;
+    dev_log!("已交换剪切板内容，暂存条目: {:?}", stashed_id);
+    Ok(stashed_id)
+}
+
+// 将条目内容导出为文件，目标目录必须已存在
+#[tauri::command]
+async fn save_item_to_file(
+    id: u64,
+    path: String,
+    storage: State<'_, SharedStorage>,
+) -> Result<(), String> {
+    let content = {
+        let storage = read_storage(&storage);
+        storage
+            .get_item_by_id(id)
+            .map(|item| item.content.clone())
+            .ok_or_else(|| i18n::t("error.item_not_found").to_string())?
+    };
+
+    let path = std::path::PathBuf::from(path);
+    let parent = path.parent().unwrap_or_else(|| std::path::Path::new(""));
+    if !parent.is_dir() {
+        return Err(i18n::tf("error.parent_dir_missing", &[&parent.display()]));
+    }
+
+    // 目前历史中只有文本条目，按 UTF-8 原样写入
+    std::fs::write(&path, content.as_bytes())
+        .map_err(|e| i18n::tf("error.save_file", &[&e]))?;
+
+    dev_log!("条目 {} 已导出到 {}", id, path.display());
+    Ok(())
+}
+
+#[tauri::command]
+async fn delete_history_item(
+    id: u64,
+    also_clear_clipboard: Option<bool>,
+    app: AppHandle,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let content = storage.get_item_by_id(id).map(|item| item.content.clone());
+    let removed = storage.remove_item(id).map_err(|e| CommandError::storage("error.delete_item", e))?;
+    if removed {
+        let _ = app.emit(events::CLIPBOARD_ITEM_REMOVED, events::ItemRemovedPayload { id });
+
+        // 被删除的正是当前剪切板内容时一并清空，避免“删掉了却还能粘贴出来”
+        if also_clear_clipboard.unwrap_or(false) {
+            let current = clipboard.get_text().ok().flatten();
+            if current.is_some() && current == content {
+                clipboard.clear()?;
+                dev_log!("已同时清空系统剪切板");
+            }
+        }
+    }
+    Ok(removed)
+}
+
+// 获取最近删除的条目（仅保存在内存中，重启后清空）
+#[tauri::command]
+async fn get_recently_deleted(storage: State<'_, SharedStorage>) -> Result<Vec<ClipboardItem>, String> {
+    let storage = read_storage(&storage);
+    Ok(storage.get_recently_deleted())
+}
+
+// 撤销最近一次删除，恢复的条目会分配新的 id
+#[tauri::command]
+async fn undo_last_delete(
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<Option<ClipboardItem>, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let restored = storage
+        .undo_last_delete()
+        .map_err(|e| CommandError::storage("error.undo_delete", e))?;
+    if let Some(item) = &restored {
+        let _ = app.emit(events::CLIPBOARD_ITEM_ADDED, events::ItemAddedPayload { item });
+    }
+    Ok(restored)
+}
+
+#[tauri::command]
+async fn set_item_favorite(
+    id: u64,
+    is_favorite: bool,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let found = storage
+        .set_item_favorite(id, is_favorite)
+        .map_err(|e| CommandError::storage("error.update_favorite", e))?;
+    if found {
+        let _ = app.emit(
+            events::CLIPBOARD_ITEM_FAVORITED,
+            events::ItemFavoritedPayload { id, is_favorite },
+        );
+    }
+    Ok(found)
+}
+
+// 按内容收藏或取消收藏，供只知道文本而不知道 id 的场景使用；返回是否找到匹配条目
+#[tauri::command]
+async fn set_favorite_by_content(
+    content: String,
+    is_favorite: bool,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    // 便签不参与收藏
+    let id = match storage.find_by_content(&content).filter(|item| !item.is_scratchpad) {
+        Some(item) => item.id,
+        None => return Ok(false),
+    };
+    let found = storage
+        .set_item_favorite(id, is_favorite)
+        .map_err(|e| CommandError::storage("error.update_favorite", e))?;
+    if found {
+        let _ = app.emit(
+            events::CLIPBOARD_ITEM_FAVORITED,
+            events::ItemFavoritedPayload { id, is_favorite },
+        );
+    }
+    Ok(found)
+}
+
+// 将历史导出为 JSON 条目数组，可选择不含收藏标记或其他元数据（仅内容与时间），返回导出条数
+#[tauri::command]
+async fn export_history(
+    path: String,
+    include_favorites: Option<bool>,
+    include_metadata: Option<bool>,
+    storage: State<'_, SharedStorage>,
+) -> Result<usize, CommandError> {
+    let items = read_storage(&storage)
+        .export_items(include_favorites.unwrap_or(true), include_metadata.unwrap_or(true));
+    let content = serde_json::to_string_pretty(&items)
+        .map_err(|e| CommandError::new("invalid_data", i18n::tf("error.export_history", &[&e])))?;
+    std::fs::write(&path, content).map_err(|e| CommandError::new("io", i18n::tf("error.save_file", &[&e])))?;
+    dev_log!("已导出 {} 条历史到 {}", items.len(), path);
+    Ok(items.len())
+}
+
+// 从 JSON 文件合并导入历史，可以是条目数组，也可以是另一份完整的数据文件；
+// 与导出对应，可选择忽略文件中的收藏标记或其他元数据
+#[tauri::command]
+async fn import_history(
+    path: String,
+    include_favorites: Option<bool>,
+    include_metadata: Option<bool>,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<storage::ImportSummary, CommandError> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum HistoryFile {
+        Items(Vec<ClipboardItem>),
+        Data { items: Vec<ClipboardItem> },
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| CommandError::new("io", i18n::tf("error.read_file", &[&e])))?;
+    let items = match serde_json::from_str::<HistoryFile>(&content)
+        .map_err(|e| CommandError::new("invalid_data", i18n::tf("error.import_history", &[&e])))?
+    {
+        HistoryFile::Items(items) | HistoryFile::Data { items } => items,
+    };
+    let (include_favorites, include_metadata) =
+        (include_favorites.unwrap_or(true), include_metadata.unwrap_or(true));
+    let items = items
+        .iter()
+        .map(|item| item.filtered(include_favorites, include_metadata))
+        .collect();
+
+    let mut storage = lock_storage(&storage);
+    let summary = storage
+        .merge_items(items)
+        .map_err(|e| CommandError::storage("error.import_history", e))?;
+    if summary.added > 0 || summary.favorite_merged > 0 {
+        let _ = app.emit(events::HISTORY_RELOADED, ());
+    }
+    dev_log!(
+        "导入历史：新增 {}，跳过 {}，合并收藏 {}",
+        summary.added,
+        summary.skipped,
+        summary.favorite_merged
+    );
+    Ok(summary)
+}
+
+// 片段文件的大小上限，避免误选大文件时整体读入内存
+const SNIPPET_FILE_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+// 从文本文件批量导入片段，按分隔符切分（默认以空行分段），返回新增与跳过的条数
+#[tauri::command]
+async fn import_snippets(
+    path: String,
+    delimiter: Option<String>,
+    favorite: bool,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<storage::ImportSummary, String> {
+    use std::io::Read;
+
+    // 多读一个字节用于判断是否超出上限，确认未超出后再按 UTF-8 解码
+    let mut bytes = Vec::new();
+    std::fs::File::open(&path)
+        .and_then(|file| file.take(SNIPPET_FILE_MAX_BYTES + 1).read_to_end(&mut bytes))
+        .map_err(|e| i18n::tf("error.read_file", &[&e]))?;
+    if bytes.len() as u64 > SNIPPET_FILE_MAX_BYTES {
+        return Err(i18n::t("error.content_too_large").to_string());
+    }
+    let content = String::from_utf8(bytes).map_err(|e| i18n::tf("error.read_file", &[&e]))?;
+    let chunks = storage::split_snippets(&content, delimiter.as_deref());
+
+    let summary = lock_storage(&storage).import_snippets(chunks, favorite);
+    if summary.added > 0 {
+        let _ = app.emit(events::HISTORY_RELOADED, ());
+    }
+    dev_log!("已从 {} 导入 {} 条片段，跳过 {}", path, summary.added, summary.skipped);
+    Ok(summary)
+}
+
+// 批量收藏或取消收藏，返回实际发生变化的条数
+#[tauri::command]
+async fn set_items_favorite(
+    ids: Vec<u64>,
+    is_favorite: bool,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<usize, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let changed = storage
+        .set_items_favorite(&ids, is_favorite)
+        .map_err(|e| CommandError::storage("error.update_favorite", e))?;
+    for &id in &changed {
+        let _ = app.emit(
+            events::CLIPBOARD_ITEM_FAVORITED,
+            events::ItemFavoritedPayload { id, is_favorite },
+        );
+    }
+    Ok(changed.len())
+}
+
+// 直接向历史添加片段（如常用回复模板），不会改动系统剪切板
+#[tauri::command]
+async fn add_snippet(
+    content: String,
+    favorite: bool,
+    tags: Option<Vec<String>>,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<ClipboardItem, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let item = storage
+        .add_snippet(content, favorite, tags.unwrap_or_default())
+        .map_err(|e| CommandError::storage("error.add_item", e))?;
+    let _ = app.emit(events::CLIPBOARD_ITEM_ADDED, events::ItemAddedPayload { item: &item });
+    Ok(item)
+}
+
+// 设置或清除条目的备注
+#[tauri::command]
+async fn set_item_note(
+    id: u64,
+    note: Option<String>,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let found = storage
+        .set_item_note(id, note)
+        .map_err(|e| CommandError::storage("error.update_note", e))?;
+    if let Some(item) = storage.get_item_by_id(id) {
+        let _ = app.emit(events::CLIPBOARD_ITEM_UPDATED, item.clone());
+    }
+    Ok(found)
+}
+
+// 设置或清除条目的颜色标记
+#[tauri::command]
+async fn set_item_color(
+    id: u64,
+    color: Option<ItemColor>,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let found = storage
+        .set_item_color(id, color)
+        .map_err(|e| CommandError::storage("error.update_color", e))?;
+    if let Some(item) = storage.get_item_by_id(id) {
+        let _ = app.emit(events::CLIPBOARD_ITEM_UPDATED, item.clone());
+    }
+    Ok(found)
 }
 
+// 清空历史的第一步：申请一个短时有效的确认令牌
 #[tauri::command]
-async fn search_clipboard_items(
-    storage: State<'_, SharedStorage>,
-    query: String,
-) -> Result<Vec<ClipboardItem>, String> {
-    let storage = storage.lock().map_err(|e| e.to_string())?;
-    let items = storage.search_items(&query);
-    Ok(items)
+async fn request_clear_history(ui_state: State<'_, UiState>) -> Result<String, String> {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let mut pending = ui_state.clear_token.lock().map_err(|e| e.to_string())?;
+    *pending = Some((token.clone(), Instant::now()));
+    Ok(token)
 }
 
 #[tauri::command]
-async fn copy_to_clipboard(
-    content: String,
+async fn clear_all_history(
+    token: Option<String>,
+    keep_favorites: Option<bool>,
+    app: AppHandle,
     storage: State<'_, SharedStorage>,
-) -> Result<(), String> {
-    use clipboard::SimpleClipboardMonitor;
-
-    let _monitor = SimpleClipboardMonitor::new(storage.inner().clone())
-        .map_err(|e| format!("创建剪切板监控器失败: {}", e))?;
-
-    // 注意：这里我们不能直接使用monitor，因为它不是mut的
-    // 我们需要创建一个临时的剪切板上下文
-    use clipboard_rs::{ClipboardContext, Clipboard};
-
-    let ctx = ClipboardContext::new()
-        .map_err(|e| format!("创建剪切板上下文失败: {}", e))?;
-
-    ctx.set_text(content)
-        .map_err(|e| format!("设置剪切板内容失败: {}", e))?;
+    ui_state: State<'_, UiState>,
+) -> Result<(), CommandError> {
+    let mut storage = lock_storage(&storage);
+
+    if storage.data.settings.require_clear_confirmation {
+        // 令牌只能使用一次，无论校验是否通过都作废
+        let pending = ui_state.clear_token.lock().map_err(|e| e.to_string())?.take();
+        let confirmed = match (pending, token) {
+            (Some((expected, issued_at)), Some(token)) => {
+                expected == token && issued_at.elapsed() < CLEAR_TOKEN_TTL
+            }
+            _ => false,
+        };
+        if !confirmed {
+            return Err(CommandError::new("needs_confirmation", i18n::t("error.clear_needs_confirmation")));
+        }
+    }
 
-    dev_log!("内容已复制到剪切板");
+    let keep_favorites = keep_favorites.unwrap_or(false);
+    storage
+        .clear_all(keep_favorites)
+        .map_err(|e| CommandError::storage("error.clear_history", e))?;
+    let _ = app.emit(events::CLIPBOARD_CLEARED, json!({ "keep_favorites": keep_favorites }));
+    dev_log!("所有历史记录已清除");
     Ok(())
 }
 
+// 只清除超过指定秒数未使用的条目，返回删除条数
 #[tauri::command]
-async fn delete_history_item(
-    id: u64,
+async fn clear_older_than(
+    secs: u64,
+    keep_favorites: Option<bool>,
+    app: AppHandle,
     storage: State<'_, SharedStorage>,
-) -> Result<bool, String> {
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
-    storage.remove_item(id).map_err(|e| format!("删除项目失败: {}", e))
+) -> Result<usize, CommandError> {
+    let removed = lock_storage(&storage)
+        .clear_older_than(secs, keep_favorites.unwrap_or(false))
+        .map_err(|e| CommandError::storage("error.clear_history", e))?;
+    if removed > 0 {
+        let _ = app.emit(events::HISTORY_RELOADED, ());
+        dev_log!("已清除 {} 条超过 {} 秒未使用的记录", removed, secs);
+    }
+    Ok(removed)
 }
 
+// 重新读取磁盘上的数据文件并重建缓存，用于文件在运行期间被外部修改后同步。
+// 全程持有存储锁，避免与后台落盘交错
 #[tauri::command]
-async fn set_item_favorite(
-    id: u64,
-    is_favorite: bool,
-    storage: State<'_, SharedStorage>,
-) -> Result<bool, String> {
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
+async fn reload_storage(app: AppHandle, storage: State<'_, SharedStorage>) -> Result<(), CommandError> {
+    let mut storage = lock_storage(&storage);
     storage
-        .set_item_favorite(id, is_favorite)
-        .map_err(|e| format!("更新置顶状态失败: {}", e))
+        .reload()
+        .map_err(|e| CommandError::storage("error.reload_storage", e))?;
+    i18n::set_locale(storage.data.settings.locale);
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
+    let _ = app.emit(events::HISTORY_RELOADED, ());
+    dev_log!("已重新加载数据文件，共 {} 条记录", storage.data.items.len());
+    Ok(())
 }
 
+// 以 JSON 形式导出当前全部数据，供外部备份工具原样保存
 #[tauri::command]
-async fn clear_all_history(
+async fn read_raw_storage(storage: State<'_, SharedStorage>) -> Result<String, CommandError> {
+    read_storage(&storage)
+        .to_raw_json()
+        .map_err(|e| CommandError::storage("error.read_raw_storage", e))
+}
+
+// 用备份工具提供的 JSON 替换全部数据，校验能解析后才替换并立即保存
+#[tauri::command]
+async fn write_raw_storage(
+    app: AppHandle,
     storage: State<'_, SharedStorage>,
-) -> Result<(), String> {
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
-    storage.clear_all().map_err(|e| format!("清除历史记录失败: {}", e))?;
-    dev_log!("所有历史记录已清除");
+    json: String,
+) -> Result<(), CommandError> {
+    let mut storage = lock_storage(&storage);
+    storage
+        .replace_from_raw_json(&json)
+        .map_err(|e| CommandError::storage("error.write_raw_storage", e))?;
+    i18n::set_locale(storage.data.settings.locale);
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
+    let _ = app.emit(events::HISTORY_RELOADED, ());
+    dev_log!("已从外部数据恢复，共 {} 条记录", storage.data.items.len());
     Ok(())
 }
 
@@ -433,52 +1352,197 @@ async fn clear_all_history(
 async fn get_settings(
     storage: State<'_, SharedStorage>,
 ) -> Result<storage::AppSettings, String> {
-    let storage = storage.lock().map_err(|e| e.to_string())?;
+    let storage = read_storage(&storage);
     Ok(storage.data.settings.clone())
 }
 
+// 描述每个设置项的类型、取值范围和默认值，前端据此生成控件并预先校验
+#[tauri::command]
+async fn get_settings_schema(
+    storage: State<'_, SharedStorage>,
+) -> Result<Vec<settings_schema::SettingSchema>, String> {
+    let storage = read_storage(&storage);
+    Ok(settings_schema::settings_schema(&storage.data.settings))
+}
+
 #[tauri::command]
 async fn update_settings(
-    settings: storage::AppSettings,
+    mut settings: storage::AppSettings,
+    app: AppHandle,
     storage: State<'_, SharedStorage>,
-) -> Result<(), String> {
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    settings.validate().map_err(CommandError::invalid_input)?;
+
+    let mut storage = lock_storage(&storage);
+    i18n::set_locale(settings.locale);
+    if storage.data.settings.session_only && !settings.session_only {
+        // 通过设置页直接关闭时丢弃会话条目，需要转存的请使用 set_session_only
+        storage
+            .set_session_only(false, false)
+            .map_err(|e| CommandError::storage("error.save_settings", e))?;
+        let _ = app.emit(events::HISTORY_RELOADED, ());
+    }
+    // 快捷键与系统全局注册相关联，只能走专门的命令修改：主快捷键由 update_shortcut 保存（重启后生效），
+    // “立即记录”快捷键由 set_capture_shortcut 即时重新注册；这里沿用当前值，避免设置与实际注册不一致
+    settings.shortcut = storage.data.settings.shortcut.clone();
+    settings.capture_shortcut = storage.data.settings.capture_shortcut.clone();
     storage.data.settings = settings;
-    storage.save().map_err(|e| format!("保存设置失败: {}", e))?;
+    storage.refresh_capture_filter();
+    storage.save().map_err(|e| CommandError::storage("error.save_settings", e))?;
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
     dev_log!("设置已更新");
     Ok(())
 }
 
+// 切换仅会话模式；关闭时 promote 为 true 则把本次会话的条目转为持久条目，返回转存条数
+#[tauri::command]
+async fn set_session_only(
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+    enabled: bool,
+    promote: Option<bool>,
+) -> Result<usize, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let promoted = storage
+        .set_session_only(enabled, promote.unwrap_or(false))
+        .map_err(|e| CommandError::storage("error.set_session_only", e))?;
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
+    if !enabled {
+        let _ = app.emit(events::HISTORY_RELOADED, ());
+    }
+    dev_log!("仅会话模式: {}，转存 {} 条", enabled, promoted);
+    Ok(promoted)
+}
+
 #[tauri::command]
 async fn update_shortcut(
     shortcut: String,
     storage: State<'_, SharedStorage>,
-) -> Result<(), String> {
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let mut storage = lock_storage(&storage);
     let shortcut_display = shortcut.clone();
     storage.data.settings.shortcut = shortcut;
-    storage.save().map_err(|e| format!("保存快捷键失败: {}", e))?;
+    storage.save().map_err(|e| CommandError::storage("error.save_shortcut", e))?;
     dev_log!("快捷键已更新为: {}", shortcut_display);
     Ok(())
 }
 
+// 获取当前平台的默认快捷键
+#[tauri::command]
+async fn get_default_shortcut() -> Result<String, String> {
+    Ok(get_platform_adapter().default_shortcut())
+}
+
+// 查询当前设置的快捷键是否已实际注册生效，供设置页显示“快捷键未生效”提示
+#[tauri::command]
+async fn is_shortcut_active(app: AppHandle, storage: State<'_, SharedStorage>) -> Result<bool, String> {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+    let shortcut = read_storage(&storage).data.settings.shortcut.clone();
+    Ok(app.global_shortcut().is_registered(shortcut.as_str()))
+}
+
+// 恢复默认快捷键：立即重新注册并保存，返回默认快捷键
+#[tauri::command]
+async fn reset_shortcut_to_default(
+    app: AppHandle,
+    shortcut_manager: State<'_, ShortcutManager>,
+    storage: State<'_, SharedStorage>,
+) -> Result<String, CommandError> {
+    let default_shortcut = get_platform_adapter().default_shortcut();
+    let current = read_storage(&storage).data.settings.shortcut.clone();
+
+    if current != default_shortcut {
+        // 当前快捷键可能本就因冲突未注册成功，注销失败无需处理
+        let _ = shortcut_manager.unregister_shortcut(&current);
+    }
+    shortcut_manager
+        .register_shortcut(&default_shortcut)
+        .map_err(|e| e.to_string())?;
+
+    let mut storage = lock_storage(&storage);
+    storage.data.settings.shortcut = default_shortcut.clone();
+    storage.save().map_err(|e| CommandError::storage("error.save_shortcut", e))?;
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
+    dev_log!("快捷键已恢复为默认: {}", default_shortcut);
+    Ok(default_shortcut)
+}
+
+// 设置“立即记录”快捷键，传入空字符串表示停用
+#[tauri::command]
+async fn set_capture_shortcut(
+    shortcut: String,
+    app: AppHandle,
+    shortcut_manager: State<'_, ShortcutManager>,
+    storage: State<'_, SharedStorage>,
+) -> Result<(), CommandError> {
+    let shortcut = shortcut.trim().to_string();
+    let (current, main_shortcut) = {
+        let storage = read_storage(&storage);
+        let settings = &storage.data.settings;
+        (settings.capture_shortcut.clone(), settings.shortcut.clone())
+    };
+    if !shortcut.is_empty() && shortcut == main_shortcut {
+        return Err(CommandError::new("conflict", i18n::t("error.capture_shortcut_conflict")));
+    }
+
+    if !current.is_empty() && current != shortcut {
+        let _ = shortcut_manager.unregister_shortcut(&current);
+    }
+    if !shortcut.is_empty() {
+        shortcut_manager
+            .register_shortcut_with(&shortcut, handle_capture_shortcut)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut storage = lock_storage(&storage);
+    storage.data.settings.capture_shortcut = shortcut;
+    storage.save().map_err(|e| CommandError::storage("error.save_shortcut", e))?;
+    let _ = app.emit(events::SETTINGS_CHANGED, storage.data.settings.clone());
+    dev_log!("“立即记录”快捷键已更新: {}", storage.data.settings.capture_shortcut);
+    Ok(())
+}
+
+// 规范化用户输入的快捷键，返回保存用的规范形式和展示用的形式
+#[tauri::command]
+async fn normalize_shortcut(input: String) -> Result<platform::NormalizedShortcut, String> {
+    platform::normalize_shortcut(&input)
+}
+
+// 在保存前探测快捷键是否被其他程序占用
+#[tauri::command]
+async fn test_shortcut_availability(
+    shortcut: String,
+    shortcut_manager: State<'_, ShortcutManager>,
+) -> Result<bool, String> {
+    shortcut_manager
+        .test_shortcut_availability(&shortcut)
+        .map_err(|e| i18n::tf("error.invalid_shortcut", &[&e]))
+}
+
 #[tauri::command]
 async fn update_max_items(
     max_items: usize,
+    app: AppHandle,
     storage: State<'_, SharedStorage>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     if max_items == 0 {
-        return Err("最大条数必须大于0".into());
+        return Err(CommandError::invalid_input(i18n::t("error.max_items_zero")));
     }
 
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
+    let mut storage = lock_storage(&storage);
     storage.data.settings.max_items = max_items;
+    // 默认显示条数不能超过新的上限
+    let settings = &mut storage.data.settings;
+    settings.default_history_limit = settings.default_history_limit.min(max_items);
     storage
         .enforce_item_limit()
-        .map_err(|e| format!("应用条数限制失败: {}", e))?;
+        .map_err(|e| CommandError::storage("error.apply_item_limit", e))?;
     storage
         .save()
-        .map_err(|e| format!("保存设置失败: {}", e))?;
+        .map_err(|e| CommandError::storage("error.save_settings", e))?;
+    // 调整上限可能淘汰部分记录，通知前端整体刷新
+    let _ = app.emit(events::HISTORY_RELOADED, ());
     dev_log!("最大记录数已更新为 {}", max_items);
     Ok(())
 }
@@ -504,7 +1568,7 @@ async fn set_hotkey_passthrough(
 async fn hide_window(
     window: tauri::WebviewWindow,
 ) -> Result<(), String> {
-    window.hide().map_err(|e| format!("隐藏窗口失败: {}", e))?;
+    window.hide().map_err(|e| i18n::tf("error.hide_window", &[&e]))?;
     Ok(())
 }
 
@@ -518,9 +1582,9 @@ async fn show_settings(
     // Ensure the front-end switches to the settings page before we bring the window forward
     dev_log!("Tray settings menu clicked");
 
-    let _ = app.emit("show-settings", ());
+    let _ = app.emit(events::SHOW_SETTINGS, ());
     if let Some(window) = app.get_webview_window("main") {
-        let _ = window.emit("show-settings", ());
+        let _ = window.emit(events::SHOW_SETTINGS, ());
     }
 
     sleep(Duration::from_millis(50)).await;
@@ -537,6 +1601,53 @@ async fn show_settings(
     Ok(())
 }
 
+// 在指定的物理坐标显示并聚焦主窗口，坐标限制在该点所在的显示器内，便于调试多显示器定位
+#[tauri::command]
+async fn show_window_at(x: i32, y: i32, app: AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| i18n::t("error.window_not_found").to_string())?;
+
+    let (x, y) = (x as f64, y as f64);
+    let monitor = window
+        .monitor_from_point(x, y)
+        .ok()
+        .flatten()
+        .or_else(|| window.current_monitor().ok().flatten());
+    let window_size = scale_window_for_monitor(&window, monitor.as_ref())
+        .map_err(|e| i18n::tf("error.show_window", &[&e]))?;
+    let (min_x, min_y, max_x, max_y) = placement_bounds(monitor.as_ref(), window_size, Some((x, y)));
+
+    let position = Position::Physical(DpiPhysicalPosition::new(
+        x.clamp(min_x, max_x).round() as i32,
+        y.clamp(min_y, max_y).round() as i32,
+    ));
+    window
+        .set_position(position)
+        .map_err(|e| i18n::tf("error.show_window", &[&e]))?;
+    remember_paste_target(&app);
+    window.show().map_err(|e| i18n::tf("error.show_window", &[&e]))?;
+    let _ = window.set_focus();
+    request_search_focus(&app);
+    Ok(())
+}
+
+// 设置搜索词并显示历史列表，供快捷启动器等外部触发方以过滤后的状态打开窗口
+#[tauri::command]
+async fn set_search_query(app: tauri::AppHandle, query: String) -> Result<(), String> {
+    let _ = app.emit(events::SET_SEARCH, query);
+    let visible = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_visible().ok())
+        .unwrap_or(false);
+    if visible {
+        let _ = app.emit(events::SHOW_HISTORY, ());
+    } else {
+        show_window_near_cursor(&app);
+    }
+    Ok(())
+}
+
 #[tauri::command]
 async fn show_history(
     app: tauri::AppHandle,
@@ -545,25 +1656,160 @@ async fn show_history(
 
     // 发送事件给前端显示历史列表
     dev_log!("托盘显示列表菜单被点击");
-    let _ = app.emit("show-history", ());
+    let _ = app.emit(events::SHOW_HISTORY, ());
     dev_log!("已发送show-history事件");
     Ok(())
 }
 
+// 找出系统剪切板当前内容对应的历史条目，用于在列表中高亮
 #[tauri::command]
-async fn type_text_to_focused_input(text: String) -> Result<(), String> {
-    use enigo::{Enigo, Settings};
-    use enigo::Keyboard;
-
-    let settings = Settings::default();
-    let mut enigo = Enigo::new(&settings).map_err(|e| format!("初始化键盘输入失败: {}", e))?;
+async fn get_current_clipboard_item(
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<Option<ClipboardItem>, String> {
+    let content = match clipboard.get_text()? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+    let storage = read_storage(&storage);
+    Ok(storage.find_by_content(&content).cloned())
+}
 
-    // 键盘输入文本
-    enigo.text(&text).map_err(|e| format!("键盘输入失败: {}", e))?;
+// 将条目写入 X11 的 PRIMARY 选区，供中键粘贴；不改动普通剪切板
+#[tauri::command]
+async fn copy_item_to_primary(
+    id: u64,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+) -> Result<(), String> {
+    let content = read_storage(&storage)
+        .get_item_by_id(id)
+        .map(|item| item.content.clone())
+        .ok_or_else(|| i18n::t("error.item_not_found").to_string())?;
+    clipboard.set_primary_text(&content)?;
+    dev_log!("条目 {} 已写入 PRIMARY 选区", id);
+    Ok(())
+}
 
+// 下一次复制不受大小上限限制，记录一次后自动恢复
+#[tauri::command]
+async fn capture_next_oversize(storage: State<'_, SharedStorage>) -> Result<(), String> {
+    lock_storage(&storage).arm_oversize_capture();
+    dev_log!("下一次复制将不受大小限制");
     Ok(())
 }
 
+// 后台监控的运行状态，便于排查是否仍在记录
+#[tauri::command]
+async fn get_monitor_status() -> Result<clipboard::MonitorStatus, String> {
+    Ok(clipboard::monitor_status())
+}
+
+// 读取系统剪切板当前内容用于预览，不会记录到历史
+#[tauri::command]
+async fn peek_clipboard(
+    clipboard: State<'_, SharedClipboard>,
+) -> Result<Option<clipboard::ClipboardPeek>, String> {
+    clipboard.peek()
+}
+
+// 列出剪切板当前内容提供的格式（text、image、html、files），便于排查未被记录的复制
+#[tauri::command]
+async fn get_clipboard_formats(clipboard: State<'_, SharedClipboard>) -> Result<Vec<String>, String> {
+    clipboard.formats()
+}
+
+#[tauri::command]
+async fn type_text_to_focused_input(
+    text: String,
+    transforms: Option<Vec<transform::TextTransform>>,
+    close_after: Option<bool>,
+    window: tauri::WebviewWindow,
+    clipboard: State<'_, SharedClipboard>,
+    storage: State<'_, SharedStorage>,
+    ui_state: State<'_, UiState>,
+) -> Result<(), String> {
+    let text = transform::apply_text_transforms(&text, &transforms.unwrap_or_default());
+    let (paste_method, restore_clipboard, typing_layout, close_after) = {
+        let storage = read_storage(&storage);
+        (
+            storage.data.settings.paste_method,
+            storage.data.settings.restore_clipboard_after_paste,
+            storage.data.settings.typing_layout,
+            close_after.unwrap_or(storage.data.settings.close_after_paste),
+        )
+    };
+
+    // 先隐藏窗口让焦点回到原应用，否则按键会发给自身
+    if window.is_visible().unwrap_or(false) {
+        window.hide().map_err(|e| i18n::tf("error.hide_window", &[&e]))?;
+        tokio::time::sleep(tokio::time::Duration::from_millis(paste::FOCUS_RETURN_DELAY_MS)).await;
+    }
+
+    let result = paste_text(&text, paste_method, restore_clipboard, typing_layout, &clipboard).await;
+
+    if !close_after {
+        // 重新显示窗口以便继续粘贴，期间焦点切换引起的失焦不自动隐藏
+        if let Ok(mut until) = ui_state.suppress_blur_hide_until.lock() {
+            *until = Some(Instant::now() + std::time::Duration::from_millis(paste::KEEP_OPEN_BLUR_GRACE_MS));
+        }
+        let _ = window.show();
+        let _ = window.set_focus();
+        dev_log!("粘贴完成，保持窗口打开");
+    }
+
+    result
+}
+
+async fn paste_text(
+    text: &str,
+    paste_method: PasteMethod,
+    restore_clipboard: bool,
+    typing_layout: storage::TypingLayout,
+    clipboard: &SharedClipboard,
+) -> Result<(), String> {
+    // 逐字输入在部分键盘布局下会把非 ASCII 字符打错，改走剪切板粘贴；
+    // 逐字输入本不改动剪切板，因此粘贴后总是恢复原内容
+    let (paste_method, restore_clipboard) = if paste_method == PasteMethod::Type
+        && typing_layout == storage::TypingLayout::Auto
+        && !text.is_ascii()
+    {
+        dev_log!("内容包含非 ASCII 字符，改用剪切板粘贴");
+        (PasteMethod::Keystroke, true)
+    } else {
+        (paste_method, restore_clipboard)
+    };
+
+    match paste_method {
+        PasteMethod::Type => paste::type_text(text),
+        PasteMethod::Keystroke => {
+            let previous = if restore_clipboard {
+                paste::read_clipboard_text(clipboard)
+            } else {
+                None
+            };
+
+            let result = paste::paste_via_keystroke(clipboard, text);
+
+            // 无论粘贴是否成功都要恢复，否则原剪切板内容会被覆盖丢失
+            if let Some(previous) = previous {
+                if result.is_ok() {
+                    // 等目标应用完成粘贴后再恢复，否则会粘贴出旧内容
+                    tokio::time::sleep(tokio::time::Duration::from_millis(paste::RESTORE_DELAY_MS)).await;
+                }
+                let restored = paste::restore_clipboard(clipboard, &previous);
+                if restored.is_ok() {
+                    dev_log!("已恢复粘贴前的剪切板内容");
+                }
+                // 粘贴本身的错误优先返回
+                result.and(restored)
+            } else {
+                result
+            }
+        }
+    }
+}
+
 #[tauri::command]
 async fn restart_app(app: tauri::AppHandle) -> Result<(), String> {
     dev_log!("重启应用程序");
@@ -576,21 +1822,23 @@ async fn restart_app(app: tauri::AppHandle) -> Result<(), String> {
         use std::env;
 
         // 获取当前可执行文件路径
-        let current_exe = env::current_exe().map_err(|e| format!("获取可执行文件路径失败: {}", e))?;
+        let current_exe = env::current_exe().map_err(|e| i18n::tf("error.current_exe", &[&e]))?;
 
         // 启动新进程
         Command::new(&current_exe)
             .args(&env::args().skip(1).collect::<Vec<_>>())
             .spawn()
-            .map_err(|e| format!("启动新进程失败: {}", e))?;
+            .map_err(|e| i18n::tf("error.spawn_process", &[&e]))?;
 
         // 退出当前进程
+        flush_storage(&app);
         std::process::exit(0);
     }
 
     #[cfg(not(debug_assertions))]
     {
         // 生产模式：使用Tauri的重启API
+        flush_storage(&app);
         app.restart();
         Ok(())
     }
@@ -598,43 +1846,24 @@ async fn restart_app(app: tauri::AppHandle) -> Result<(), String> {
 
 // 按需检查剪切板变化的命令（开发模式友好）
 #[tauri::command]
-async fn check_clipboard_changes(storage: State<'_, SharedStorage>) -> Result<Option<ClipboardItem>, String> {
-    use clipboard_rs::{ClipboardContext, Clipboard};
-
-    let ctx = ClipboardContext::new()
-        .map_err(|e| format!("创建剪切板上下文失败: {}", e))?;
-
-    if let Ok(content) = ctx.get_text() {
-        if !content.trim().is_empty() {
-            // 检查内容是否已经存在
-            if let Ok(mut storage) = storage.lock() {
-                let existing_items = storage.get_all_items();
-
-                // 检查是否与最新项目重复
-                if let Some(latest) = existing_items.first() {
-                    if latest.content == content {
-                        return Ok(None); // 内容未变化
-                    }
-                }
+async fn check_clipboard_changes(app: AppHandle) -> Result<Option<ClipboardItem>, String> {
+    Ok(capture_clipboard_once(&app))
+}
 
-                // 添加新项目，克隆内容避免所有权移动
-                let content_clone = content.clone();
-                if let Ok(item_id) = storage.add_item(content) {
-                    return Ok(Some(ClipboardItem {
-                        id: item_id,
-                        content: content_clone,
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                        is_favorite: false,
-                    }));
-                }
-            }
-        }
+// “立即记录”快捷键：不论监控是否开启，记录一次当前剪切板内容
+fn handle_capture_shortcut(app: &AppHandle) {
+    if capture_clipboard_once(app).is_some() {
+        dev_log!("已手动记录当前剪切板内容");
+    } else {
+        dev_log!("手动记录：剪切板内容为空、与最新记录相同或被过滤");
     }
+}
 
-    Ok(None)
+// 读取当前剪切板并记录为新条目，内容为空、重复或被过滤时返回 None
+fn capture_clipboard_once(app: &AppHandle) -> Option<ClipboardItem> {
+    let clipboard = app.state::<SharedClipboard>().inner().clone();
+    let storage = app.state::<SharedStorage>().inner().clone();
+    clipboard::capture_once(storage, clipboard, Some(app))
 }
 
 // 启动/停止剪切板监控（仅在开发模式下使用）
@@ -657,27 +1886,193 @@ async fn toggle_clipboard_monitoring(enable: bool) -> Result<bool, String> {
     Ok(MONITOR_ENABLED.load(Ordering::SeqCst))
 }
 
+// 获取始终置顶的便签
+#[tauri::command]
+async fn get_scratchpad(storage: State<'_, SharedStorage>) -> Result<Option<ClipboardItem>, String> {
+    let storage = read_storage(&storage);
+    Ok(storage.get_scratchpad().cloned())
+}
+
+// 更新便签内容
+#[tauri::command]
+async fn set_scratchpad(
+    content: String,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<ClipboardItem, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let scratchpad = storage
+        .set_scratchpad(content)
+        .map_err(|e| CommandError::storage("error.save_scratchpad", e))?;
+    let _ = app.emit(events::CLIPBOARD_ITEM_UPDATED, scratchpad.clone());
+    Ok(scratchpad)
+}
+
+// 获取全部收藏夹
+#[tauri::command]
+async fn get_collections(storage: State<'_, SharedStorage>) -> Result<Vec<Collection>, String> {
+    let storage = read_storage(&storage);
+    Ok(storage.get_collections().to_vec())
+}
+
+// 新建收藏夹
+#[tauri::command]
+async fn create_collection(name: String, storage: State<'_, SharedStorage>) -> Result<Collection, CommandError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input(i18n::t("error.collection_name_empty")));
+    }
+    let mut storage = lock_storage(&storage);
+    storage
+        .create_collection(name)
+        .map_err(|e| CommandError::storage("error.update_collection", e))
+}
+
+// 将条目加入收藏夹，收藏夹中的条目不会被自动清理
+#[tauri::command]
+async fn add_to_collection(
+    collection_id: u64,
+    item_id: u64,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    storage
+        .add_to_collection(collection_id, item_id)
+        .map_err(|e| CommandError::storage("error.update_collection", e))
+}
+
+// 将条目移出收藏夹，条目本身保留在历史中
+#[tauri::command]
+async fn remove_from_collection(
+    collection_id: u64,
+    item_id: u64,
+    storage: State<'_, SharedStorage>,
+) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
+    storage
+        .remove_from_collection(collection_id, item_id)
+        .map_err(|e| CommandError::storage("error.update_collection", e))
+}
+
+// 获取收藏夹中的条目
+#[tauri::command]
+async fn get_collection_items(
+    collection_id: u64,
+    storage: State<'_, SharedStorage>,
+) -> Result<Vec<ClipboardItem>, String> {
+    let storage = read_storage(&storage);
+    storage
+        .get_collection_items(collection_id)
+        .ok_or_else(|| i18n::t("error.collection_not_found").to_string())
+}
+
+// 获取 id 大于 after_id 的新条目，用于前端增量刷新；返回的 cursor 作为下次的 after_id
+#[tauri::command]
+async fn get_items_since(
+    after_id: u64,
+    storage: State<'_, SharedStorage>,
+) -> Result<storage::ItemsSince, String> {
+    let storage = read_storage(&storage);
+    Ok(storage.get_items_since(after_id))
+}
+
 // 获取剪切板数据最后更新时间
 #[tauri::command]
 async fn get_last_updated(storage: State<'_, SharedStorage>) -> Result<u64, String> {
-    let storage = storage.lock().unwrap();
+    let storage = read_storage(&storage);
     Ok(storage.get_last_updated())
 }
 
+// 获取历史的修改序号，每次修改都会递增，前端轮询据此判断是否需要重新拉取
+#[tauri::command]
+async fn get_revision(storage: State<'_, SharedStorage>) -> Result<u64, String> {
+    Ok(read_storage(&storage).revision())
+}
+
+// 将数据文件迁移到新目录，返回新的文件路径；remove_old 为 true 时删除旧文件
+#[tauri::command]
+async fn relocate_storage(
+    new_dir: String,
+    remove_old: Option<bool>,
+    storage: State<'_, SharedStorage>,
+) -> Result<String, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let new_path = storage
+        .relocate(std::path::Path::new(&new_dir), remove_old.unwrap_or(false))
+        .map_err(|e| CommandError::storage("error.relocate_storage", e))?;
+    dev_log!("数据文件已迁移到: {}", new_path.display());
+    Ok(new_path.display().to_string())
+}
+
+// 自检数据文件，repair 为 true 时修复可自动处理的问题
+#[tauri::command]
+async fn verify_storage(
+    repair: Option<bool>,
+    app: AppHandle,
+    storage: State<'_, SharedStorage>,
+) -> Result<storage::StorageReport, CommandError> {
+    let mut storage = lock_storage(&storage);
+    let report = storage
+        .verify(repair.unwrap_or(false))
+        .map_err(|e| CommandError::storage("error.verify_storage", e))?;
+    if report.repaired > 0 {
+        let _ = app.emit(events::HISTORY_RELOADED, ());
+    }
+    Ok(report)
+}
+
+// 本地使用统计，utc_offset_minutes 由前端按本地时区传入，缺省按 UTC 统计
+#[tauri::command]
+async fn get_usage_summary(
+    days: usize,
+    utc_offset_minutes: Option<i32>,
+    storage: State<'_, SharedStorage>,
+) -> Result<storage::UsageSummary, String> {
+    let offset_secs = utc_offset_minutes.unwrap_or(0) as i64 * 60;
+    read_storage(&storage)
+        .usage_summary(days, offset_secs)
+        .map_err(|e| e.to_string())
+}
+
+// 按系统时区格式化条目时间，供各平台前端统一显示相对时间
+#[tauri::command]
+async fn format_timestamp(ts: u64) -> Result<String, String> {
+    Ok(clock::format_timestamp(ts))
+}
+
+// 获取数据文件位置等存储状态
+#[tauri::command]
+async fn get_storage_stats(storage: State<'_, SharedStorage>) -> Result<storage::StorageStats, String> {
+    let storage = read_storage(&storage);
+    Ok(storage.stats())
+}
+
 // 检查是否首次启动
 #[tauri::command]
-async fn check_first_launch(storage: State<'_, SharedStorage>) -> Result<bool, String> {
-    let mut storage = storage.lock().map_err(|e| e.to_string())?;
+async fn check_first_launch(storage: State<'_, SharedStorage>) -> Result<bool, CommandError> {
+    let mut storage = lock_storage(&storage);
     let is_first = storage.data.is_first_launch;
     if is_first {
         storage.data.is_first_launch = false;
         storage
             .save()
-            .map_err(|e| format!("更新首次启动状态失败: {}", e))?;
+            .map_err(|e| CommandError::storage("error.update_first_launch", e))?;
     }
     Ok(is_first)
 }
 
+// 重新标记为首次启动，下次检查时再次进入引导流程
+#[tauri::command]
+async fn reset_first_launch(storage: State<'_, SharedStorage>) -> Result<(), CommandError> {
+    let mut storage = lock_storage(&storage);
+    storage.data.is_first_launch = true;
+    storage
+        .save()
+        .map_err(|e| CommandError::storage("error.update_first_launch", e))?;
+    dev_log!("已重置首次启动标记");
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 创建共享存储
@@ -689,56 +2084,175 @@ pub fn run() {
         }
     };
 
-    let shared_storage = Arc::new(Mutex::new(storage));
+    i18n::set_locale(storage.data.settings.locale);
+    let shared_storage = Arc::new(RwLock::new(storage));
+    storage::install_panic_hook(shared_storage.clone());
 
     // 使用事件驱动的剪切板监控，避免后台线程与热重载冲突
     dev_log!("剪切板监控切换为事件驱动模式");
     // 暂时不启动后台监控，等应用完全启动后再开启
 
     tauri::Builder::default()
+        // 单实例：再次启动时通知已运行的实例切换窗口后退出，避免重复注册快捷键
+        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            dev_log!("检测到重复启动，切换已运行实例的窗口");
+            handle_app_toggle(app);
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_fs::init())
         .manage(shared_storage)
+        .manage(SharedClipboard::default())
         .manage(UiState::default())
         .invoke_handler(tauri::generate_handler![
             get_clipboard_history,
             get_all_clipboard_items,
+            get_history_summaries,
+            get_sorted_history,
+            set_sort_mode,
+            get_item,
+            get_items_by_ids,
             search_clipboard_items,
+            count_search_matches,
+            get_item_stats,
             copy_to_clipboard,
+            set_clipboard_silent,
+            copy_item_transformed,
+            swap_clipboard_with_item,
+            copy_item_with_formats,
+            pipe_item_to_command,
+            append_to_clipboard,
+            save_item_to_file,
             type_text_to_focused_input,
             delete_history_item,
+            get_recently_deleted,
+            undo_last_delete,
             set_item_favorite,
+            set_item_color,
+            set_item_note,
+            set_items_favorite,
+            set_favorite_by_content,
+            add_snippet,
+            import_snippets,
+            export_history,
+            import_history,
+            peek_clipboard,
+            get_clipboard_formats,
+            get_monitor_status,
+            capture_next_oversize,
+            get_current_clipboard_item,
+            copy_item_to_primary,
+            request_clear_history,
             clear_all_history,
+            clear_older_than,
+            reload_storage,
+            read_raw_storage,
+            write_raw_storage,
             get_settings,
             update_settings,
+            set_session_only,
             update_shortcut,
+            test_shortcut_availability,
+            get_default_shortcut,
+            normalize_shortcut,
+            get_settings_schema,
+            is_shortcut_active,
+            reset_shortcut_to_default,
+            set_capture_shortcut,
             update_max_items,
             set_hotkey_passthrough,
             hide_window,
             show_settings,
             show_history,
+            set_search_query,
+            show_window_at,
             restart_app,
             check_clipboard_changes,
             toggle_clipboard_monitoring,
             get_last_updated,
+            get_revision,
+            get_items_since,
+            get_scratchpad,
+            set_scratchpad,
+            get_collections,
+            create_collection,
+            add_to_collection,
+            remove_from_collection,
+            get_collection_items,
             check_first_launch,
+            reset_first_launch,
+            get_usage_summary,
+            format_timestamp,
+            get_storage_stats,
+            verify_storage,
+            relocate_storage,
             platform_commands::get_platform_info,
             platform_commands::check_permissions,
             platform_commands::request_permission,
-            platform_commands::open_system_settings
+            platform_commands::open_system_settings,
+            platform_commands::pin_window_above_fullscreen
         ])
         .setup(|app| {
+            let app_handle = app.handle().clone();
+            storage::spawn_autosave(app.state::<SharedStorage>().inner().clone(), move |failures, e| {
+                eprintln!("剪切板数据已连续 {} 次保存失败", failures);
+                let _ = app_handle.emit(
+                    events::STORAGE_SAVE_FAILED,
+                    json!({ "failures": failures, "error": e.to_string() }),
+                );
+            });
+
+            let maintenance = spawn_maintenance_scheduler(app.handle().clone());
+            app.manage(MaintenanceTask(Mutex::new(Some(maintenance))));
+
             // 在生产模式下启动后台剪切板监控
             #[cfg(not(debug_assertions))]
             {
                 let storage = app.state::<SharedStorage>();
                 let app_handle = app.handle().clone();
-                if let Err(e) = clipboard::start_clipboard_monitoring_with_events(storage.inner().clone(), Some(app_handle)) {
+                let shared_clipboard = app.state::<SharedClipboard>();
+                if let Err(e) = clipboard::start_clipboard_monitoring_with_events(
+                    storage.inner().clone(),
+                    shared_clipboard.inner().clone(),
+                    Some(app_handle),
+                ) {
                     eprintln!("启动剪切板监控失败: {}", e);
                 }
             }
 
+            // 按需启动本地脚本接口（修改设置后需重启生效）
+            {
+                let storage = app.state::<SharedStorage>();
+                let api_config = {
+                    let mut storage = lock_storage(&storage);
+                    if storage.data.settings.enable_local_api {
+                        if storage.data.settings.local_api_token.is_empty() {
+                            storage.data.settings.local_api_token = local_api::generate_token();
+                            if let Err(e) = storage.save() {
+                                eprintln!("保存本地接口令牌失败: {}", e);
+                            }
+                        }
+                        Some((
+                            storage.data.settings.local_api_port,
+                            storage.data.settings.local_api_token.clone(),
+                        ))
+                    } else {
+                        None
+                    }
+                };
+
+                if let Some((port, token)) = api_config {
+                    if let Err(e) = local_api::start_local_api(
+                        storage.inner().clone(),
+                        port,
+                        token,
+                        Some(app.handle().clone()),
+                    ) {
+                        eprintln!("{}", e);
+                    }
+                }
+            }
+
             // 注册全局快捷键
             #[cfg(desktop)]
             {
@@ -746,6 +2260,7 @@ pub fn run() {
 
                 // 创建快捷键管理器
                 let shortcut_manager = ShortcutManager::new(app_handle.clone());
+                app.manage(shortcut_manager.clone());
 
                 // 先清理可能存在的残留快捷键
                 if let Err(e) = shortcut_manager.cleanup_residual_shortcuts() {
@@ -753,11 +2268,18 @@ pub fn run() {
                 }
 
                 // 从存储中读取用户设置的快捷键
-                let user_shortcut = {
+                let (user_shortcut, capture_shortcut) = {
                     let storage = app.state::<SharedStorage>();
-                    let storage = storage.lock().unwrap();
-                    storage.data.settings.shortcut.clone()
+                    let storage = read_storage(&storage);
+                    let settings = &storage.data.settings;
+                    (settings.shortcut.clone(), settings.capture_shortcut.clone())
                 };
+
+                if !capture_shortcut.is_empty() {
+                    if let Err(e) = shortcut_manager.register_shortcut_with(&capture_shortcut, handle_capture_shortcut) {
+                        eprintln!("注册“立即记录”快捷键失败: {}", e);
+                    }
+                }
                 let shortcut_to_register = user_shortcut;
 
                 // 尝试注册快捷键
@@ -775,9 +2297,11 @@ pub fn run() {
                             tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
 
                             // 发送快捷键冲突事件到前端（不显示窗口，只通过系统托盘通知）
-                            let _ = app_handle_clone.emit("shortcut-conflict", json!({
-                                "message": format!("快捷键 {} 已被其他程序占用", shortcut_conflict),
-                                "suggestion": "请通过系统托盘右键菜单打开设置，修改为其他快捷键组合"
+                            let _ = app_handle_clone.emit(events::SHORTCUT_CONFLICT, json!({
+                                "message": i18n::tf("shortcut.conflict_message", &[&shortcut_conflict]),
+                                "suggestion": i18n::t("shortcut.conflict_suggestion"),
+                                "shortcut": shortcut_conflict,
+                                "active": false
                             }));
                         });
                     }
@@ -787,8 +2311,13 @@ pub fn run() {
                 let icon_image = build_tray_icon_image();
                 let window = app.get_webview_window("main").unwrap();
                 let _ = window.set_icon(icon_image.clone());
+                if let Err(e) = platform_commands::pin_window_above_fullscreen(window.clone()) {
+                    eprintln!("{}", e);
+                }
                 let window_clone = window.clone();
                 let move_state = app.state::<UiState>().last_window_move.clone();
+                let blur_grace = app.state::<UiState>().suppress_blur_hide_until.clone();
+                let paste_target = app.state::<UiState>().paste_target.clone();
 
                 window.on_window_event(move |event| {
                     match event {
@@ -803,6 +2332,10 @@ pub fn run() {
                             }
                         }
                         tauri::WindowEvent::Focused(focused) => {
+                            // 托管模式下窗口生命周期完全交给窗口管理器，失焦不做任何处理
+                            if current_window_mode(window_clone.app_handle()) == WindowMode::Managed {
+                                return;
+                            }
                             if !focused && window_clone.is_visible().unwrap_or(false) {
                                 let suppress_hide = move_state
                                     .lock()
@@ -812,9 +2345,29 @@ pub fn run() {
                                             .unwrap_or(false)
                                     })
                                     .unwrap_or(false);
+                                let keep_open = blur_grace
+                                    .lock()
+                                    .map(|until| until.map(|until| Instant::now() < until).unwrap_or(false))
+                                    .unwrap_or(false);
+                                let pinned = read_storage(&window_clone.state::<SharedStorage>())
+                                    .data
+                                    .settings
+                                    .pin_to_paste_target;
+                                let back_to_target = pinned
+                                    && paste_target
+                                        .lock()
+                                        .map(|target| {
+                                            target.is_some()
+                                                && *target == get_platform_adapter().foreground_window()
+                                        })
+                                        .unwrap_or(false);
 
                                 if suppress_hide {
                                     dev_log!("窗口拖动中，跳过自动隐藏");
+                                } else if keep_open {
+                                    dev_log!("粘贴后保持窗口打开，跳过自动隐藏");
+                                } else if back_to_target {
+                                    dev_log!("焦点回到粘贴目标，跳过自动隐藏");
                                 } else {
                                     dev_log!("窗口失去焦点，自动隐藏");
                                     let _ = window_clone.hide();
@@ -830,11 +2383,11 @@ pub fn run() {
                 use tauri::tray::TrayIconBuilder;
 
                 // 创建菜单项
-                let show_item = MenuItem::with_id(app, "show", "显示/隐藏", true, None::<&str>)
+                let show_item = MenuItem::with_id(app, "show", i18n::t("tray.show"), true, None::<&str>)
                     .unwrap();
-                let settings_item = MenuItem::with_id(app, "settings", "设置", true, None::<&str>)
+                let settings_item = MenuItem::with_id(app, "settings", i18n::t("tray.settings"), true, None::<&str>)
                     .unwrap();
-                let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)
+                let quit_item = MenuItem::with_id(app, "quit", i18n::t("tray.quit"), true, None::<&str>)
                     .unwrap();
 
                 // 创建托盘菜单
@@ -850,10 +2403,10 @@ pub fn run() {
 
 
                 // 创建托盘图标
-                let _tray_icon = TrayIconBuilder::with_id("main-tray")
+                let tray_icon = TrayIconBuilder::with_id("main-tray")
                     .icon(tray_icon_image)
                     .menu(&tray_menu)
-                    .tooltip("剪切板管理器")
+                    .tooltip(i18n::t("tray.tooltip"))
                     .on_menu_event(move |app, event| {
                         match event.id().as_ref() {
                             "show" => {
@@ -862,16 +2415,7 @@ pub fn run() {
                                     if window.is_visible().unwrap_or(false) {
                                         let _ = window.hide();
                                     } else {
-                                        if let Ok(pos) = app.cursor_position() {
-                                            position_window_near_cursor(
-                                                &window,
-                                                DpiPhysicalPosition::new(pos.x, pos.y),
-                                            );
-                                        } else {
-                                            let _ = window.center();
-                                        }
-                                        let _ = window.show();
-                                        let _ = window.set_focus();
+                                        show_window_near_cursor(app);
                                     }
                                 }
                             }
@@ -884,6 +2428,7 @@ pub fn run() {
                                 });
                             }
                             "quit" => {
+                                flush_storage(app);
                                 std::process::exit(0);
                             }
                             _ => {}
@@ -892,6 +2437,20 @@ pub fn run() {
                     .build(app)
                     .unwrap();
 
+                app.manage(TrayState { tray: tray_icon });
+                update_tray_tooltip(app.handle());
+
+                // 历史条数变化时刷新托盘提示。事件可能在持有存储锁时发出，放到异步任务中避免死锁
+                for event in events::HISTORY_CHANGE_EVENTS {
+                    let app_handle = app.handle().clone();
+                    app.listen(event, move |_| {
+                        let app_handle = app_handle.clone();
+                        tauri::async_runtime::spawn(async move {
+                            update_tray_tooltip(&app_handle);
+                        });
+                    });
+                }
+
                 dev_log!("系统托盘已初始化");
 
   
@@ -904,6 +2463,16 @@ pub fn run() {
             }
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(task) = app.try_state::<MaintenanceTask>() {
+                    if let Some(handle) = task.0.lock().ok().and_then(|mut handle| handle.take()) {
+                        handle.abort();
+                    }
+                }
+                flush_storage(app);
+            }
+        });
 }
@@ -0,0 +1,189 @@
+use std::io::Read;
+
+use serde::Deserialize;
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::events;
+use crate::i18n;
+use crate::storage::{lock_storage, read_storage, CaptureKind, HistoryOrder, SharedStorage};
+
+// 本地脚本接口，仅监听 127.0.0.1，需要携带 `Authorization: Bearer <token>`
+//
+//   GET  /history?limit=N  获取历史记录
+//   POST /items            新增记录，body 为纯文本或 {"content": "..."}；
+//                          超出大小上限返回 413，历史关闭返回 409，不符合记录规则返回 422
+//   GET  /items/latest     获取最新一条记录
+
+#[derive(Deserialize)]
+struct NewItemBody {
+    content: String,
+}
+
+// 生成新的接口令牌
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+pub fn start_local_api(
+    storage: SharedStorage,
+    port: u16,
+    token: String,
+    app_handle: Option<AppHandle>,
+) -> Result<(), String> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| i18n::tf("api.start_failed", &[&e]))?;
+
+    std::thread::spawn(move || {
+        dev_log!("本地接口已启动: http://127.0.0.1:{}", port);
+        for request in server.incoming_requests() {
+            handle_request(request, &storage, &token, app_handle.as_ref());
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_request(
+    mut request: Request,
+    storage: &SharedStorage,
+    token: &str,
+    app_handle: Option<&AppHandle>,
+) {
+    if !is_authorized(&request, token) {
+        respond(request, 401, json!({ "error": i18n::t("api.unauthorized") }));
+        return;
+    }
+
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+
+    match (&method, path) {
+        (Method::Get, "/history") => {
+            let limit = query
+                .split('&')
+                .filter_map(|pair| pair.strip_prefix("limit="))
+                .find_map(|value| value.parse::<usize>().ok())
+                .unwrap_or(100);
+            let items = read_storage(storage).get_history(limit, HistoryOrder::default());
+            respond(request, 200, json!(items));
+        }
+        (Method::Get, "/items/latest") => {
+            // 最近一次捕获，不含便签
+            let latest = read_storage(storage).latest_item().cloned();
+            match latest {
+                Some(item) => respond(request, 200, json!(item)),
+                None => respond(request, 404, json!({ "error": i18n::t("api.no_items") })),
+            }
+        }
+        (Method::Post, "/items") => {
+            // 请求体不超过文本大小上限，多读一个字节用于判断是否超出；
+            // 先按字节判断大小再解码，截断处落在多字节字符中间时也能返回 413
+            let limit = read_storage(storage).data.settings.limit_for(CaptureKind::Text);
+            let mut bytes = Vec::new();
+            let mut reader = request.as_reader().take(limit as u64 + 1);
+            if let Err(e) = reader.read_to_end(&mut bytes) {
+                respond(request, 400, json!({ "error": i18n::tf("api.read_body_failed", &[&e]) }));
+                return;
+            }
+            if bytes.len() > limit {
+                respond(request, 413, json!({ "error": i18n::t("error.content_too_large") }));
+                return;
+            }
+            let body = match String::from_utf8(bytes) {
+                Ok(body) => body,
+                Err(e) => {
+                    respond(request, 400, json!({ "error": i18n::tf("api.read_body_failed", &[&e]) }));
+                    return;
+                }
+            };
+
+            let content = serde_json::from_str::<NewItemBody>(&body)
+                .map(|parsed| parsed.content)
+                .unwrap_or(body);
+            if content.trim().is_empty() {
+                respond(request, 400, json!({ "error": i18n::t("api.empty_content") }));
+                return;
+            }
+
+            // 与监控捕获相同：历史关闭或不符合记录规则（内容类型、仅记录规则）时不写入
+            let result = {
+                let mut storage = lock_storage(storage);
+                if !storage.data.settings.history_enabled {
+                    drop(storage);
+                    respond(request, 409, json!({ "error": i18n::t("api.history_disabled") }));
+                    return;
+                }
+                if !storage.should_capture(&content) {
+                    drop(storage);
+                    respond(request, 422, json!({ "error": i18n::t("api.capture_filtered") }));
+                    return;
+                }
+                storage
+                    .add_item(content)
+                    .map(|id| storage.get_item_by_id(id).cloned())
+            };
+
+            match result {
+                Ok(Some(item)) => {
+                    if let Some(app) = app_handle {
+                        let _ = app.emit(
+                            events::CLIPBOARD_ITEM_ADDED,
+                            events::ItemAddedPayload { item: &item },
+                        );
+                        let _ = app.emit(events::CLIPBOARD_UPDATED, item.clone());
+                    }
+                    respond(request, 201, json!(item));
+                }
+                Ok(None) => respond(request, 500, json!({ "error": i18n::t("api.add_failed") })),
+                Err(e) => respond(request, 400, json!({ "error": e.to_string() })),
+            }
+        }
+        _ => respond(request, 404, json!({ "error": i18n::t("api.unknown_endpoint") })),
+    }
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|value| constant_time_eq(value.as_bytes(), token.as_bytes()))
+    })
+}
+
+// 逐字节比较全部内容，耗时与第一个不同字节的位置无关，避免通过响应时间猜测令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn respond(request: Request, status: u16, body: serde_json::Value) {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("静态请求头必然合法");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        eprintln!("本地接口响应失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_comparison_requires_exact_match() {
+        assert!(constant_time_eq(b"abc123", b"abc123"));
+        assert!(!constant_time_eq(b"abc123", b"abc124"));
+        assert!(!constant_time_eq(b"abc123", b"abc12"));
+        assert!(!constant_time_eq(b"", b"abc123"));
+    }
+}
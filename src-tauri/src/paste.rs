@@ -0,0 +1,56 @@
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+use crate::clipboard::ClipboardService;
+use crate::i18n;
+
+// 模拟粘贴后等待多久再恢复原剪切板，过早恢复会导致目标应用粘贴到旧内容
+pub const RESTORE_DELAY_MS: u64 = 300;
+
+// 隐藏窗口后等待焦点回到原应用再粘贴
+pub const FOCUS_RETURN_DELAY_MS: u64 = 100;
+
+// 粘贴后保持窗口打开时，重新显示窗口期间暂停失焦自动隐藏的时长
+pub const KEEP_OPEN_BLUR_GRACE_MS: u64 = 500;
+
+// 逐字模拟键盘输入，长文本较慢，部分输入法下可能出错
+pub fn type_text(text: &str) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| i18n::tf("error.init_keyboard", &[&e]))?;
+
+    enigo.text(text).map_err(|e| i18n::tf("error.type_text", &[&e]))
+}
+
+// 先写入剪切板，再模拟平台粘贴快捷键；写入登记为预期写入，不会被监控重复记录
+pub fn paste_via_keystroke(clipboard: &ClipboardService, text: &str) -> Result<(), String> {
+    clipboard.set_text_silent(text)?;
+
+    send_paste_keystroke().map_err(|e| i18n::tf("error.paste_keystroke", &[&e]))
+}
+
+fn send_paste_keystroke() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let modifier = Key::Meta;
+    #[cfg(not(target_os = "macos"))]
+    let modifier = Key::Control;
+
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| i18n::tf("error.init_keyboard", &[&e]))?;
+
+    enigo.key(modifier, Direction::Press).map_err(|e| e.to_string())?;
+    let result = enigo.key(Key::Unicode('v'), Direction::Click);
+    // 无论粘贴是否成功都要松开修饰键，避免按键卡住
+    enigo.key(modifier, Direction::Release).map_err(|e| e.to_string())?;
+    result.map_err(|e| e.to_string())
+}
+
+// 读取当前剪切板文本，用于粘贴后恢复；非文本内容无法恢复，返回 None
+pub fn read_clipboard_text(clipboard: &ClipboardService) -> Option<String> {
+    clipboard.get_text().ok().flatten()
+}
+
+// 恢复粘贴前的内容，同样走预期写入，避免旧内容被当作新复制重新置顶
+pub fn restore_clipboard(clipboard: &ClipboardService, previous: &str) -> Result<(), String> {
+    clipboard
+        .set_text_silent(previous)
+        .map_err(|e| i18n::tf("error.restore_clipboard", &[&e]))
+}
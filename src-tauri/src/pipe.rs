@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+
+use crate::i18n;
+
+// 外部命令的最长执行时间
+pub const PIPE_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 允许列表中的一条：可执行文件的绝对路径及其固定参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowedCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl AllowedCommand {
+    // 解析形如 `/usr/bin/xclip -selection clipboard` 的条目，参数以空白分隔；
+    // 路径含空格时用双引号包住，如 `"C:\Program Files\tool.exe" --stdin`。
+    // 程序必须是绝对路径，不经 PATH 查找，避免被同名程序顶替
+    pub fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim();
+        let (program, rest) = match entry.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"')?,
+            None => entry.split_once(char::is_whitespace).unwrap_or((entry, "")),
+        };
+        let program = PathBuf::from(program);
+        if !program.is_absolute() {
+            return None;
+        }
+        Some(Self {
+            program,
+            args: rest.split_whitespace().map(str::to_string).collect(),
+        })
+    }
+}
+
+// 在允许列表中查找程序与参数都和调用方完全一致的条目，调用方不能追加或改动参数
+pub fn resolve(allowlist: &[String], command: &str, args: &[String]) -> Result<AllowedCommand, String> {
+    allowlist
+        .iter()
+        .filter_map(|entry| AllowedCommand::parse(entry))
+        .find(|allowed| allowed.program == Path::new(command) && allowed.args == args)
+        .ok_or_else(|| i18n::tf("error.command_not_allowed", &[&command]))
+}
+
+pub fn check_size(content: &str, limit: usize) -> Result<(), String> {
+    if content.len() > limit {
+        return Err(i18n::t("error.content_too_large").to_string());
+    }
+    Ok(())
+}
+
+// 启动命令并把内容写入其标准输入，超过 timeout 仍未结束时终止命令
+pub async fn run(command: &AllowedCommand, content: &str, timeout: Duration) -> Result<(), String> {
+    let name = command.program.display();
+    let mut child = tokio::process::Command::new(&command.program)
+        .args(&command.args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| i18n::tf("error.run_command", &[&name, &e]))?;
+
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content.as_bytes()).await?;
+            // 关闭标准输入，让命令知道内容已结束
+            drop(stdin);
+        }
+        child.wait_with_output().await
+    };
+    let output = tokio::time::timeout(timeout, run)
+        .await
+        .map_err(|_| i18n::tf("error.command_timeout", &[&name]))?
+        .map_err(|e| i18n::tf("error.run_command", &[&name, &e]))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(i18n::tf("error.command_failed", &[&name, &output.status, &stderr.trim()]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist() -> Vec<String> {
+        vec![
+            "/usr/bin/xclip -selection clipboard".to_string(),
+            "xclip".to_string(),
+            r#""/opt/my tools/copy" --stdin"#.to_string(),
+        ]
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parses_program_and_fixed_args() {
+        let parsed = AllowedCommand::parse(r#""/opt/my tools/copy" --stdin"#).unwrap();
+        assert_eq!(parsed.program, PathBuf::from("/opt/my tools/copy"));
+        assert_eq!(parsed.args, args(&["--stdin"]));
+        assert!(AllowedCommand::parse("xclip -i").is_none());
+        assert!(AllowedCommand::parse("").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolves_only_exact_entries() {
+        let list = allowlist();
+        assert!(resolve(&list, "/usr/bin/xclip", &args(&["-selection", "clipboard"])).is_ok());
+        assert!(resolve(&list, "/opt/my tools/copy", &args(&["--stdin"])).is_ok());
+    }
+
+    #[test]
+    fn rejects_commands_and_args_not_on_the_list() {
+        let list = allowlist();
+        // 不在列表中
+        assert!(resolve(&list, "/bin/sh", &args(&["-c", "echo hi"])).is_err());
+        // 追加参数或省略固定参数
+        assert!(resolve(&list, "/usr/bin/xclip", &args(&["-selection", "clipboard", "-o"])).is_err());
+        assert!(resolve(&list, "/usr/bin/xclip", &[]).is_err());
+        // 相对路径条目不生效，不会经 PATH 查找
+        assert!(resolve(&list, "xclip", &[]).is_err());
+        assert!(resolve(&[], "/usr/bin/xclip", &args(&["-selection", "clipboard"])).is_err());
+    }
+
+    #[test]
+    fn size_guard_uses_the_given_limit() {
+        assert!(check_size("abcd", 4).is_ok());
+        assert!(check_size("abcde", 4).is_err());
+        // 按字节而非字符计算
+        assert!(check_size("中文", 4).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn long_running_command_times_out() {
+        let sleep = AllowedCommand { program: PathBuf::from("/bin/sleep"), args: args(&["5"]) };
+        let started = std::time::Instant::now();
+        assert!(run(&sleep, "", Duration::from_millis(100)).await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn content_reaches_stdin() {
+        let cat = AllowedCommand { program: PathBuf::from("/bin/cat"), args: Vec::new() };
+        assert!(run(&cat, "hello", PIPE_COMMAND_TIMEOUT).await.is_ok());
+        let fail = AllowedCommand { program: PathBuf::from("/bin/sh"), args: args(&["-c", "exit 1"]) };
+        assert!(run(&fail, "", PIPE_COMMAND_TIMEOUT).await.is_err());
+    }
+}
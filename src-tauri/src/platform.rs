@@ -1,4 +1,4 @@
-use tauri::AppHandle;
+use tauri::{AppHandle, WebviewWindow};
 
 /// 平台特定权限状态
 #[derive(Debug, Clone)]
@@ -43,6 +43,15 @@ pub trait PlatformAdapter {
 
     /// 获取推荐窗口样式
     fn get_window_style(&self) -> WindowStyle;
+
+    /// 让窗口可以显示在全屏应用之上
+    fn pin_above_fullscreen(&self, window: &WebviewWindow) -> Result<(), String>;
+
+    /// 获取剪切板变更序列号，仅在内容真正变化时递增；不支持的平台返回None
+    fn clipboard_sequence(&self) -> Option<u64>;
+
+    /// 获取当前前台窗口（或应用）的标识，用于识别粘贴目标；不支持的平台返回None
+    fn foreground_window(&self) -> Option<u64>;
 }
 
 /// 窗口样式配置
@@ -118,6 +127,34 @@ impl PlatformAdapter for WindowsPlatform {
             always_on_top: true,
         }
     }
+
+    fn pin_above_fullscreen(&self, _window: &WebviewWindow) -> Result<(), String> {
+        // Windows的置顶窗口本身就能覆盖全屏应用
+        Ok(())
+    }
+
+    fn clipboard_sequence(&self) -> Option<u64> {
+        #[cfg(target_os = "windows")]
+        {
+            use windows_sys::Win32::System::DataExchange::GetClipboardSequenceNumber;
+            // 无权访问剪切板时返回0，此时退回到内容比较
+            let sequence = unsafe { GetClipboardSequenceNumber() };
+            (sequence != 0).then_some(sequence as u64)
+        }
+        #[cfg(not(target_os = "windows"))]
+        None
+    }
+
+    fn foreground_window(&self) -> Option<u64> {
+        #[cfg(target_os = "windows")]
+        {
+            use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+            let hwnd = unsafe { GetForegroundWindow() };
+            (!hwnd.is_null()).then_some(hwnd as u64)
+        }
+        #[cfg(not(target_os = "windows"))]
+        None
+    }
 }
 
 /// macOS平台实现
@@ -172,20 +209,7 @@ impl PlatformAdapter for MacOSPlatform {
                 // macOS需要辅助功能权限用于全局快捷键
                 #[cfg(target_os = "macos")]
                 {
-                    let message = format!(
-                        "需要启用辅助功能权限：\n\
-                        1. 打开系统偏好设置\n\
-                        2. 进入「安全性与隐私」\n\
-                        3. 选择「隐私」标签\n\
-                        4. 找到「辅助功能」并勾选 {}\n\
-                        5. 重启应用以使权限生效",
-                        std::env::current_exe()
-                            .unwrap_or_else(|_| "应用程序".into())
-                            .file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                    );
-                    Err(message)
+                    Err(crate::i18n::tf("permission.accessibility_steps", &[&app_display_name()]))
                 }
                 #[cfg(not(target_os = "macos"))]
                 Ok(())
@@ -194,19 +218,7 @@ impl PlatformAdapter for MacOSPlatform {
                 // macOS 10.14+ 需要通知权限
                 #[cfg(target_os = "macos")]
                 {
-                    let message = format!(
-                        "需要启用通知权限：\n\
-                        1. 打开系统偏好设置\n\
-                        2. 进入「通知」\n\
-                        3. 在左侧找到 {}\n\
-                        4. 允许发送通知",
-                        std::env::current_exe()
-                            .unwrap_or_else(|_| "应用程序".into())
-                            .file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                    );
-                    Err(message)
+                    Err(crate::i18n::tf("permission.notification_steps", &[&app_display_name()]))
                 }
                 #[cfg(not(target_os = "macos"))]
                 Ok(())
@@ -242,6 +254,81 @@ impl PlatformAdapter for MacOSPlatform {
             always_on_top: true,
         }
     }
+
+    fn pin_above_fullscreen(&self, window: &WebviewWindow) -> Result<(), String> {
+        // 全屏应用位于独立的Space中，仅置顶不足以覆盖，需要调整NSWindow的collectionBehavior
+        #[cfg(target_os = "macos")]
+        {
+            use objc::runtime::Object;
+            use objc::{msg_send, sel, sel_impl};
+
+            // NSWindowCollectionBehaviorCanJoinAllSpaces | NSWindowCollectionBehaviorFullScreenAuxiliary
+            const CAN_JOIN_ALL_SPACES: u64 = 1 << 0;
+            const FULL_SCREEN_AUXILIARY: u64 = 1 << 8;
+
+            let ns_window = window
+                .ns_window()
+                .map_err(|e| crate::i18n::tf("error.ns_window", &[&e]))? as *mut Object;
+
+            unsafe {
+                let behavior: u64 = msg_send![ns_window, collectionBehavior];
+                let _: () = msg_send![
+                    ns_window,
+                    setCollectionBehavior: behavior | CAN_JOIN_ALL_SPACES | FULL_SCREEN_AUXILIARY
+                ];
+            }
+            Ok(())
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            let _ = window;
+            Ok(())
+        }
+    }
+
+    fn clipboard_sequence(&self) -> Option<u64> {
+        // NSPasteboard的changeCount与Windows序列号语义一致
+        #[cfg(target_os = "macos")]
+        {
+            use objc::runtime::Object;
+            use objc::{class, msg_send, sel, sel_impl};
+
+            unsafe {
+                let pasteboard: *mut Object = msg_send![class!(NSPasteboard), generalPasteboard];
+                if pasteboard.is_null() {
+                    return None;
+                }
+                let change_count: i64 = msg_send![pasteboard, changeCount];
+                Some(change_count as u64)
+            }
+        }
+        #[cfg(not(target_os = "macos"))]
+        None
+    }
+
+    fn foreground_window(&self) -> Option<u64> {
+        // 以前台应用的进程号作为标识
+        #[cfg(target_os = "macos")]
+        {
+            use objc::runtime::Object;
+            use objc::{class, msg_send, sel, sel_impl};
+
+            unsafe {
+                let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+                if workspace.is_null() {
+                    return None;
+                }
+                let app: *mut Object = msg_send![workspace, frontmostApplication];
+                if app.is_null() {
+                    return None;
+                }
+                let pid: i32 = msg_send![app, processIdentifier];
+                (pid > 0).then_some(pid as u64)
+            }
+        }
+        #[cfg(not(target_os = "macos"))]
+        None
+    }
 }
 
 /// Linux平台实现
@@ -303,6 +390,30 @@ impl PlatformAdapter for LinuxPlatform {
             always_on_top: true,
         }
     }
+
+    fn pin_above_fullscreen(&self, _window: &WebviewWindow) -> Result<(), String> {
+        // 由窗口管理器决定全屏时的层级
+        Ok(())
+    }
+
+    fn clipboard_sequence(&self) -> Option<u64> {
+        // X11/Wayland没有等价的序列号，依赖内容比较
+        None
+    }
+
+    fn foreground_window(&self) -> Option<u64> {
+        // Wayland不允许查询其他应用的窗口，X11也需要额外依赖，暂不支持
+        None
+    }
+}
+
+/// 获取用于权限说明的应用名称
+#[cfg(target_os = "macos")]
+fn app_display_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| crate::i18n::t("app.fallback_name").to_string())
 }
 
 /// 获取当前平台的适配器
@@ -330,12 +441,78 @@ pub fn get_platform_adapter() -> Box<dyn PlatformAdapter> {
 pub fn get_shortcut_display_text(shortcut: &str) -> String {
     #[cfg(target_os = "macos")]
     {
-        shortcut.replace("Cmd", "⌘").replace("Alt", "⌥").replace("Shift", "⇧")
+        shortcut
+            .replace("CmdOrCtrl", "Cmd")
+            .replace("Cmd", "⌘")
+            .replace("Alt", "⌥")
+            .replace("Shift", "⇧")
     }
     #[cfg(not(target_os = "macos"))]
     {
-        shortcut.to_string()
+        shortcut.replace("CmdOrCtrl", "Ctrl")
+    }
+}
+
+/// 快捷键的规范形式（用于保存）与展示形式
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NormalizedShortcut {
+    pub canonical: String,
+    pub display: String,
+}
+
+/// 统一修饰键的写法、大小写与顺序，如 "alt+shift+v" 与 "Shift+Alt+V" 都规范为 "Alt+Shift+V"
+pub fn normalize_shortcut(input: &str) -> Result<NormalizedShortcut, String> {
+    use tauri_plugin_global_shortcut::Shortcut;
+
+    // 规范顺序即数组顺序
+    const MODIFIERS: [(&str, &[&str]); 5] = [
+        ("CmdOrCtrl", &["cmdorctrl", "commandorcontrol", "cmdorcontrol", "commandorctrl"]),
+        ("Ctrl", &["ctrl", "control"]),
+        ("Cmd", &["cmd", "command", "super", "meta"]),
+        ("Alt", &["alt", "option"]),
+        ("Shift", &["shift"]),
+    ];
+
+    let invalid = || crate::i18n::tf("error.invalid_shortcut", &[&input]);
+
+    let mut modifiers = [false; MODIFIERS.len()];
+    let mut key: Option<String> = None;
+    for part in input.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(invalid());
+        }
+        let lower = part.to_lowercase();
+        match MODIFIERS.iter().position(|(_, aliases)| aliases.contains(&lower.as_str())) {
+            Some(index) => modifiers[index] = true,
+            // 只允许一个非修饰键
+            None if key.is_some() => return Err(invalid()),
+            None => {
+                let mut chars = lower.chars();
+                key = chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect());
+            }
+        }
     }
+
+    let key = key.ok_or_else(invalid)?;
+    let canonical = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, used)| *used)
+        .map(|((name, _), _)| *name)
+        .chain(std::iter::once(key.as_str()))
+        .collect::<Vec<_>>()
+        .join("+");
+
+    canonical
+        .parse::<Shortcut>()
+        .map_err(|e| crate::i18n::tf("error.invalid_shortcut", &[&e]))?;
+
+    Ok(NormalizedShortcut {
+        display: get_shortcut_display_text(&canonical),
+        canonical,
+    })
 }
 
 /// 检查权限并返回用户友好的错误信息
@@ -354,17 +531,17 @@ pub fn check_permissions_with_user_friendly_errors() -> Vec<String> {
     {
         let accessibility_status = adapter.check_permission(Permission::Accessibility);
         if matches!(accessibility_status, PermissionStatus::Denied) {
-            errors.push(format!(
-                "{} 需要辅助功能权限来监听全局快捷键。请在系统设置中启用。",
-                adapter.platform_name()
+            errors.push(crate::i18n::tf(
+                "permission.accessibility_required",
+                &[&adapter.platform_name()],
             ));
         }
 
         let notification_status = adapter.check_permission(Permission::Notification);
         if matches!(notification_status, PermissionStatus::Denied) {
-            errors.push(format!(
-                "{} 需要通知权限来显示剪切板操作提示。",
-                adapter.platform_name()
+            errors.push(crate::i18n::tf(
+                "permission.notification_required",
+                &[&adapter.platform_name()],
             ));
         }
     }
@@ -1,4 +1,5 @@
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, WebviewWindow};
+use crate::i18n;
 use crate::platform::{get_platform_adapter, Permission};
 
 /// 获取平台信息
@@ -34,14 +35,22 @@ pub fn request_permission(app: AppHandle, permission_type: String) -> Result<Str
         "notification" => Permission::Notification,
         "clipboard" => Permission::Clipboard,
         "global_shortcut" => Permission::GlobalShortcut,
-        _ => return Err("未知的权限类型".to_string()),
+        _ => return Err(i18n::t("permission.unknown_type").to_string()),
     };
 
     let adapter = get_platform_adapter();
     adapter.request_permission(&app, permission)
-        .map_err(|e| format!("请求权限失败: {}", e))?;
+        .map_err(|e| i18n::tf("permission.request_failed", &[&e]))?;
 
-    Ok("权限请求已处理".to_string())
+    Ok(i18n::t("permission.request_handled").to_string())
+}
+
+/// 让主窗口显示在全屏应用之上（仅macOS生效）
+#[tauri::command]
+pub fn pin_window_above_fullscreen(window: WebviewWindow) -> Result<(), String> {
+    let adapter = get_platform_adapter();
+    adapter.pin_above_fullscreen(&window)
+        .map_err(|e| i18n::tf("error.pin_above_fullscreen", &[&e]))
 }
 
 /// 打开系统设置
@@ -67,12 +76,12 @@ pub fn open_system_settings(setting_type: String) -> Result<String, String> {
                     .args(&["x-apple.systempreferences:com.apple.preference.security"])
                     .spawn()
             }
-            _ => return Err("未知的设置类型".to_string()),
+            _ => return Err(i18n::t("settings.unknown_type").to_string()),
         };
 
         match command {
-            Ok(_) => Ok("系统设置已打开".to_string()),
-            Err(e) => Err(format!("打开系统设置失败: {}", e)),
+            Ok(_) => Ok(i18n::t("settings.opened").to_string()),
+            Err(e) => Err(i18n::tf("settings.open_failed", &[&e])),
         }
     }
 
@@ -84,11 +93,11 @@ pub fn open_system_settings(setting_type: String) -> Result<String, String> {
             "notifications" => {
                 use std::process::Command;
                 match Command::new("ms-settings:notifications").spawn() {
-                    Ok(_) => Ok("通知设置已打开".to_string()),
-                    Err(e) => Err(format!("打开通知设置失败: {}", e)),
+                    Ok(_) => Ok(i18n::t("settings.notifications_opened").to_string()),
+                    Err(e) => Err(i18n::tf("settings.notifications_open_failed", &[&e])),
                 }
             }
-            _ => Ok("Windows平台无需特殊设置".to_string()),
+            _ => Ok(i18n::t("settings.windows_not_required").to_string()),
         }
     }
 
@@ -106,10 +115,10 @@ pub fn open_system_settings(setting_type: String) -> Result<String, String> {
 
         for (cmd, args) in commands {
             if let Ok(_) = Command::new(cmd).args(args).spawn() {
-                return Ok("系统设置已打开".to_string());
+                return Ok(i18n::t("settings.opened").to_string());
             }
         }
 
-        Err("无法打开系统设置，请手动打开隐私设置".to_string())
+        Err(i18n::t("settings.open_manually").to_string())
     }
 }
\ No newline at end of file
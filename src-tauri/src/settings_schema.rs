@@ -0,0 +1,173 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i18n::Locale;
+use crate::storage::{
+    AppSettings, CaptureKind, DedupMode, PasteMethod, PreviewStyle, SortMode, TypingLayout, WindowAnchor,
+    WindowMode, MAX_SAVE_RETRIES, WINDOW_OPACITY_RANGE,
+};
+
+// 设置项的取值类型，前端据此选择输入控件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingType {
+    Bool,
+    Integer,
+    Number,
+    String,
+    // 单选，取值见 allowed
+    Enum,
+    StringList,
+    // 多选，取值见 allowed
+    EnumList,
+    // 以内容类型为键的映射，值的取值见 allowed
+    EnumMap,
+    // 以内容类型为键、整数为值的映射，值的范围见 min、max
+    IntegerMap,
+}
+
+// 单个设置项的描述
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: SettingType,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub allowed: Vec<String>,
+    pub default: Value,
+}
+
+impl SettingSchema {
+    fn new(name: &str, kind: SettingType) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            min: None,
+            max: None,
+            allowed: Vec::new(),
+            default: Value::Null,
+        }
+    }
+
+    fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    fn allowed<T: Serialize>(mut self, variants: &[T]) -> Self {
+        // 通过序列化取得取值，与 serde 的命名保持一致
+        self.allowed = variants
+            .iter()
+            .filter_map(|variant| match serde_json::to_value(variant) {
+                Ok(Value::String(value)) => Some(value),
+                _ => None,
+            })
+            .collect();
+        self
+    }
+}
+
+// 根据当前设置生成描述；部分上限依赖其他设置（如默认显示条数不超过最大条数）
+pub fn settings_schema(current: &AppSettings) -> Vec<SettingSchema> {
+    let mut schema = vec![
+        SettingSchema::new("max_items", SettingType::Integer).min(1.0),
+        SettingSchema::new("max_size_mb", SettingType::Integer).min(1.0),
+        SettingSchema::new("auto_start", SettingType::Bool),
+        SettingSchema::new("shortcut", SettingType::String),
+        SettingSchema::new("capture_shortcut", SettingType::String),
+        SettingSchema::new("window_anchor", SettingType::Enum).allowed(&[
+            WindowAnchor::Cursor,
+            WindowAnchor::TopRight,
+            WindowAnchor::BottomRight,
+            WindowAnchor::Center,
+            WindowAnchor::Tray,
+        ]),
+        SettingSchema::new("paste_method", SettingType::Enum)
+            .allowed(&[PasteMethod::Type, PasteMethod::Keystroke]),
+        SettingSchema::new("restore_clipboard_after_paste", SettingType::Bool),
+        SettingSchema::new("require_clear_confirmation", SettingType::Bool),
+        SettingSchema::new("locale", SettingType::Enum).allowed(&[Locale::ZhCn, Locale::En]),
+        SettingSchema::new("enable_local_api", SettingType::Bool),
+        SettingSchema::new("local_api_port", SettingType::Integer).range(1.0, u16::MAX as f64),
+        SettingSchema::new("local_api_token", SettingType::String),
+        SettingSchema::new("window_opacity", SettingType::Number)
+            .range(*WINDOW_OPACITY_RANGE.start(), *WINDOW_OPACITY_RANGE.end()),
+        SettingSchema::new("fetch_link_titles", SettingType::Bool),
+        SettingSchema::new("default_history_limit", SettingType::Integer)
+            .range(1.0, current.max_items.max(1) as f64),
+        SettingSchema::new("dedup_window_secs", SettingType::Integer).min(0.0),
+        SettingSchema::new("dedup_mode", SettingType::Enum)
+            .allowed(&[DedupMode::Exact, DedupMode::Normalized]),
+        SettingSchema::new("dedup_modes", SettingType::EnumMap)
+            .allowed(&[DedupMode::Exact, DedupMode::Normalized]),
+        SettingSchema::new("max_content_kb", SettingType::IntegerMap).min(1.0),
+        SettingSchema::new("capture_only_patterns", SettingType::StringList),
+        SettingSchema::new("capture_primary_selection", SettingType::Bool),
+        SettingSchema::new("history_enabled", SettingType::Bool),
+        SettingSchema::new("session_only", SettingType::Bool),
+        SettingSchema::new("capture_custom_formats", SettingType::StringList),
+        SettingSchema::new("pipe_command_allowlist", SettingType::StringList),
+        SettingSchema::new("max_item_age_days", SettingType::Integer).min(0.0),
+        SettingSchema::new("maintenance_idle_secs", SettingType::Integer).min(0.0),
+        SettingSchema::new("save_retries", SettingType::Integer).range(0.0, MAX_SAVE_RETRIES as f64),
+        SettingSchema::new("sort_mode", SettingType::Enum).allowed(&[
+            SortMode::Recent,
+            SortMode::FavoritesFirst,
+            SortMode::MostUsed,
+            SortMode::Alphabetical,
+        ]),
+        SettingSchema::new("close_after_paste", SettingType::Bool),
+        SettingSchema::new("preview_style", SettingType::Enum)
+            .allowed(&[PreviewStyle::Lines, PreviewStyle::Compact]),
+        SettingSchema::new("pin_to_paste_target", SettingType::Bool),
+        SettingSchema::new("focus_search_on_show", SettingType::Bool),
+        SettingSchema::new("window_mode", SettingType::Enum)
+            .allowed(&[WindowMode::Popup, WindowMode::Managed]),
+        SettingSchema::new("typing_layout", SettingType::Enum)
+            .allowed(&[TypingLayout::Auto, TypingLayout::Unicode]),
+        SettingSchema::new("capture_kinds", SettingType::EnumList).allowed(&[
+            CaptureKind::Text,
+            CaptureKind::Image,
+            CaptureKind::Html,
+            CaptureKind::Files,
+        ]),
+    ];
+
+    let defaults = match serde_json::to_value(AppSettings::default()) {
+        Ok(Value::Object(defaults)) => defaults,
+        _ => return schema,
+    };
+
+    for entry in schema.iter_mut() {
+        if let Some(default) = defaults.get(&entry.name) {
+            entry.default = default.clone();
+        }
+    }
+
+    // 新增设置忘记在上面登记时，按默认值推断类型，保证前端至少能看到它
+    for (name, default) in defaults.iter() {
+        if schema.iter().any(|entry| &entry.name == name) {
+            continue;
+        }
+        let kind = match default {
+            Value::Bool(_) => SettingType::Bool,
+            Value::Number(number) if number.is_f64() => SettingType::Number,
+            Value::Number(_) => SettingType::Integer,
+            Value::Array(_) => SettingType::StringList,
+            _ => SettingType::String,
+        };
+        dev_log!("设置项 {} 未登记描述，按默认值推断为 {:?}", name, kind);
+        let mut entry = SettingSchema::new(name, kind);
+        entry.default = default.clone();
+        schema.push(entry);
+    }
+
+    schema
+}
@@ -1,19 +1,217 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::clock::now_secs;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
 use dirs::{data_dir, data_local_dir, config_dir};
+use crate::i18n::{tf, Locale};
+use thiserror::Error;
+use regex::RegexSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("{}", crate::i18n::t("error.content_too_large"))]
+    TooLarge,
+    #[error("{}", tf("error.storage_io", &[.0]))]
+    Io(#[from] std::io::Error),
+    #[error("{}", tf("error.storage_parse", &[.0]))]
+    Serde(#[from] serde_json::Error),
+    #[error("{}", tf("error.item_id_not_found", &[.0]))]
+    NotFound(u64),
+    #[error("{}", tf("error.storage_exists", &[.0]))]
+    AlreadyExists(String),
+    // 设置未通过校验，消息已本地化
+    #[error("{0}")]
+    InvalidSettings(String),
+}
+
+impl StorageError {
+    /// 稳定的错误类别，供前端按类型区分处理，不随语言变化
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StorageError::TooLarge => "too_large",
+            StorageError::Io(_) => "io",
+            StorageError::Serde(_) => "invalid_data",
+            StorageError::NotFound(_) => "not_found",
+            StorageError::AlreadyExists(_) => "already_exists",
+            StorageError::InvalidSettings(_) => "invalid_input",
+        }
+    }
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardItem {
     pub id: u64,
     pub content: String,
-    pub timestamp: u64,
+    // 首次捕获时间
+    #[serde(default)]
+    pub created_at: u64,
+    // 最近一次使用时间；序列化时沿用旧的 timestamp 字段名，兼容旧数据与前端
+    #[serde(rename = "timestamp", alias = "last_used_at")]
+    pub last_used_at: u64,
     pub is_favorite: bool,
+    #[serde(default)]
+    pub is_scratchpad: bool,
+    #[serde(default)]
+    pub line_count: usize,
+    // 前几行内容，便于前端折叠多行条目时直接展示
+    #[serde(default)]
+    pub preview_lines: Vec<String>,
+    // 折叠空白后的单行预览，适合缩进较多的代码和 JSON
+    #[serde(default)]
+    pub compact_preview: String,
+    // 颜色标记，仅用于前端展示分组
+    #[serde(default)]
+    pub color_label: Option<ItemColor>,
+    // 被复制的次数，重复复制刷新条目时累加
+    #[serde(default = "default_use_count")]
+    pub use_count: u32,
+    // 内容来源，用于区分普通剪切板与 X11 选区
+    #[serde(default)]
+    pub source: CaptureSource,
+    // 用户添加的标签，目前仅手动添加的片段会带有
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // 用户填写的备注，说明条目用途，可被搜索
+    #[serde(default)]
+    pub note: Option<String>,
+    // 按设置额外记录的自定义格式数据，普通条目为空，为空时不写入数据文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_formats: Vec<ExtraFormat>,
+}
+
+// 自定义剪切板格式的数据，以 base64 保存，便于写入 JSON 和在前端传递
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtraFormat {
+    pub mime: String,
+    pub base64: String,
+}
+
+// 条目的捕获来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    #[default]
+    Clipboard,
+    PrimarySelection,
+    // 用户手动添加的片段，并非复制得到
+    Snippet,
+}
+
+fn default_use_count() -> u32 {
+    1
+}
+
+// 条目可选的颜色标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
+}
+
+// 便签条目使用保留 id，普通条目的 id 从 1 开始分配
+pub const SCRATCHPAD_ID: u64 = 0;
+
+// 数据文件名，以及默认目录中记录迁移后位置的文件名
+const DATA_FILE_NAME: &str = "clipboard_data.json";
+
+// 当前数据格式版本，新增需要回填的字段时递增并在 migrate 中补充对应步骤
+pub const SCHEMA_VERSION: u32 = 3;
+
+// 当前应用版本，每次保存时写入数据文件
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+const LOCATION_FILE_NAME: &str = "data_location";
+
+// 即使没有新捕获，也至少每隔这么久维护一次，使保留天数规则生效
+const MAINTENANCE_MAX_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// 内存中保留的最近删除条目数
+pub const RECENTLY_DELETED_LIMIT: usize = 10;
+
+// 预览保留的最大行数
+pub const PREVIEW_LINE_LIMIT: usize = 3;
+
+// 预览中每行保留的最大字符数，避免压缩过的超长单行内容拖慢界面
+pub const PREVIEW_LINE_CHARS: usize = 200;
+
+impl ClipboardItem {
+    pub fn new(id: u64, content: String, timestamp: u64) -> Self {
+        let mut item = Self {
+            id,
+            content,
+            created_at: timestamp,
+            last_used_at: timestamp,
+            is_favorite: false,
+            is_scratchpad: false,
+            line_count: 0,
+            preview_lines: Vec::new(),
+            compact_preview: String::new(),
+            color_label: None,
+            use_count: 1,
+            source: CaptureSource::default(),
+            tags: Vec::new(),
+            note: None,
+            extra_formats: Vec::new(),
+        };
+        item.refresh_metadata();
+        item
+    }
+
+    // 再次被复制时刷新时间并累加次数
+    pub fn touch(&mut self, timestamp: u64) {
+        self.last_used_at = timestamp;
+        self.use_count = self.use_count.saturating_add(1);
+    }
+
+    // 导出、导入时按需去掉个人标记：不含元数据时只保留内容与时间
+    pub fn filtered(&self, include_favorites: bool, include_metadata: bool) -> Self {
+        let mut item = if include_metadata {
+            self.clone()
+        } else {
+            let mut bare = Self::new(self.id, self.content.clone(), self.last_used_at);
+            bare.created_at = self.created_at;
+            bare.is_favorite = self.is_favorite;
+            bare
+        };
+        if !include_favorites {
+            item.is_favorite = false;
+        }
+        item
+    }
+
+    // 根据内容重新计算行数与预览
+    pub fn refresh_metadata(&mut self) {
+        self.line_count = self.content.lines().count();
+        self.preview_lines = self
+            .content
+            .lines()
+            .take(PREVIEW_LINE_LIMIT)
+            .map(truncate_preview_line)
+            .collect();
+        self.compact_preview = compact_preview(&self.content);
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// 用户命名的收藏夹，只保存条目 id 的引用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: u64,
+    pub name: String,
+    pub item_ids: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardData {
     pub items: Vec<ClipboardItem>,
     pub next_id: u64,
@@ -21,6 +219,34 @@ pub struct ClipboardData {
     pub last_updated: u64,
     #[serde(default)]
     pub is_first_launch: bool,
+    // 始终置顶的便签，单独存放，不参与去重和条数限制
+    #[serde(default)]
+    pub scratchpad: Option<ClipboardItem>,
+    #[serde(default)]
+    pub collections: Vec<Collection>,
+    // 数据格式版本，低于 SCHEMA_VERSION 时加载会执行一次性迁移
+    #[serde(default)]
+    pub schema_version: u32,
+    // 最后一次写入数据文件的应用版本，用于排查升级、降级问题
+    #[serde(default)]
+    pub last_written_by_version: String,
+}
+
+impl ClipboardData {
+    // 首次启动时的空数据
+    fn first_launch() -> Self {
+        Self {
+            items: Vec::new(),
+            next_id: 1,
+            settings: AppSettings::default(),
+            last_updated: now_secs(),
+            is_first_launch: true,
+            scratchpad: None,
+            collections: Vec::new(),
+            schema_version: SCHEMA_VERSION,
+            last_written_by_version: String::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,266 +255,2457 @@ pub struct AppSettings {
     pub max_size_mb: usize,
     pub auto_start: bool,
     pub shortcut: String,
+    #[serde(default)]
+    pub window_anchor: WindowAnchor,
+    #[serde(default)]
+    pub paste_method: PasteMethod,
+    #[serde(default)]
+    pub restore_clipboard_after_paste: bool,
+    #[serde(default = "default_true")]
+    pub require_clear_confirmation: bool,
+    #[serde(default = "Locale::detect")]
+    pub locale: Locale,
+    #[serde(default)]
+    pub enable_local_api: bool,
+    #[serde(default = "default_local_api_port")]
+    pub local_api_port: u16,
+    #[serde(default)]
+    pub local_api_token: String,
+    #[serde(default = "default_window_opacity")]
+    pub window_opacity: f64,
+    #[serde(default)]
+    pub fetch_link_titles: bool,
+    #[serde(default = "default_history_limit")]
+    pub default_history_limit: usize,
+    // 同一内容在该时间窗口内再次复制时只刷新原条目，0 表示关闭
+    #[serde(default)]
+    pub dedup_window_secs: u64,
+    // 非空时只记录匹配其中任一正则的内容
+    #[serde(default)]
+    pub capture_only_patterns: Vec<String>,
+    // 仅 X11：同时记录 PRIMARY 选区（选中即复制、中键粘贴）的内容
+    #[serde(default)]
+    pub capture_primary_selection: bool,
+    // 超过该天数未使用的条目会在空闲维护时删除，0 表示不限制
+    #[serde(default)]
+    pub max_item_age_days: u64,
+    // 无新捕获持续多少秒后执行清理维护
+    #[serde(default = "default_maintenance_idle_secs")]
+    pub maintenance_idle_secs: u64,
+    // 历史列表的排序方式
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    // 粘贴后是否隐藏窗口；关闭后可连续粘贴多个条目
+    #[serde(default = "default_true")]
+    pub close_after_paste: bool,
+    // 需要记录的内容类型，未勾选的格式监控时直接忽略
+    #[serde(default = "default_capture_kinds")]
+    pub capture_kinds: Vec<CaptureKind>,
+    // 逐字输入时如何处理当前键盘布局无法直接输入的字符
+    #[serde(default)]
+    pub typing_layout: TypingLayout,
+    // 窗口管理方式，平铺式窗口管理器下可交由其管理
+    #[serde(default)]
+    pub window_mode: WindowMode,
+    // 判断重复内容的方式，按内容类型单独配置，未配置的类型使用 dedup_mode
+    #[serde(default)]
+    pub dedup_mode: DedupMode,
+    #[serde(default)]
+    pub dedup_modes: HashMap<CaptureKind, DedupMode>,
+    // 保存遇到暂时性读写错误（如文件被占用）时的重试次数，每次重试间隔翻倍，总等待不超过 SAVE_RETRY_MAX_WAIT
+    #[serde(default = "default_save_retries")]
+    pub save_retries: u32,
+    // “立即记录”快捷键，按下时记录当前剪切板内容（不受监控开关影响），为空表示不启用
+    #[serde(default)]
+    pub capture_shortcut: String,
+    // 记住呼出窗口前的前台应用（粘贴目标），切回该应用时不自动隐藏，切到其他应用仍会隐藏
+    #[serde(default)]
+    pub pin_to_paste_target: bool,
+    // 额外记录的自定义剪切板格式（如编辑器专用的片段格式），复制条目时一并恢复；为空表示不记录
+    #[serde(default)]
+    pub capture_custom_formats: Vec<String>,
+    // 历史列表的预览样式
+    #[serde(default)]
+    pub preview_style: PreviewStyle,
+    // 允许通过 pipe_item_to_command 调用的外部命令，每条为可执行文件的绝对路径加固定参数，
+    // 如 `/usr/bin/xclip -selection clipboard`；为空表示不允许
+    #[serde(default)]
+    pub pipe_command_allowlist: Vec<String>,
+    // 按内容类型限制单条记录的大小（KB），未配置的类型使用默认上限；超出限制的内容不记录
+    #[serde(default = "default_max_content_kb")]
+    pub max_content_kb: HashMap<CaptureKind, u64>,
+    // 每次显示窗口时让搜索框获得焦点，呼出后可直接输入过滤
+    #[serde(default = "default_true")]
+    pub focus_search_on_show: bool,
+    // 为 false 时监控仍会把当前剪切板内容推送给前端预览，但不写入历史
+    #[serde(default = "default_true")]
+    pub history_enabled: bool,
+    // 仅会话模式：新捕获的内容只保存在内存中，退出即丢弃，不写入数据文件
+    #[serde(default)]
+    pub session_only: bool,
 }
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        // 使用平台适配器获取默认快捷键
-        let adapter = crate::platform::get_platform_adapter();
-        Self {
-            max_items: 100,
-            max_size_mb: 50,
-            auto_start: false,
-            shortcut: adapter.default_shortcut(),
-        }
-    }
+fn default_true() -> bool {
+    true
 }
 
-pub struct SimpleStorage {
-    file_path: PathBuf,
-    pub data: ClipboardData,
+fn default_local_api_port() -> u16 {
+    27_810
 }
 
-impl SimpleStorage {
-    pub fn resolve_storage_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let mut candidates = Vec::new();
-        candidates.push(data_local_dir());
-        candidates.push(data_dir());
-        candidates.push(config_dir());
+fn default_window_opacity() -> f64 {
+    1.0
+}
 
-        for candidate in candidates.into_iter().flatten() {
-            let mut base = candidate.clone();
-            base.push("clipper");
-            if fs::create_dir_all(&base).is_ok() {
-                base.push("clipboard_data.json");
-                return Ok(base);
-            }
-        }
+fn default_history_limit() -> usize {
+    100
+}
 
-        let mut fallback = std::env::current_dir()?;
-        fallback.push(".clipper");
-        fs::create_dir_all(&fallback)?;
-        fallback.push("clipboard_data.json");
-        Ok(fallback)
-    }
+fn default_capture_kinds() -> Vec<CaptureKind> {
+    vec![CaptureKind::Text]
+}
 
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let mut path = Self::resolve_storage_path()?;
+fn default_maintenance_idle_secs() -> u64 {
+    30
+}
 
-        if !path.exists() {
-            let mut legacy = std::env::current_dir()?;
-            legacy.push("clipboard_data.json");
-            if legacy.exists() {
-                if let Err(err) = fs::copy(&legacy, &path) {
-                    eprintln!("迁移旧版剪切板数据失败: {}", err);
-                }
-            }
-        }
+fn default_save_retries() -> u32 {
+    3
+}
 
-        let data = if path.exists() {
-            let content = fs::read_to_string(&path)?;
+// 图片通常远大于文本，默认放宽到 10MB
+fn default_max_content_kb() -> HashMap<CaptureKind, u64> {
+    HashMap::from([
+        (CaptureKind::Text, 1024),
+        (CaptureKind::Image, 10 * 1024),
+        (CaptureKind::Html, 1024),
+        (CaptureKind::Files, 1024),
+    ])
+}
 
-            // 首先尝试解析为完整结构
-            match serde_json::from_str::<ClipboardData>(&content) {
-                Ok(mut data) => {
-                    // 如果成功解析但没有last_updated字段，添加当前时间
-                    if data.last_updated == 0 {
-                        data.last_updated = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)?
-                            .as_secs();
-                        // 立即保存更新的数据
-                        let updated_content = serde_json::to_string_pretty(&data)?;
-                        fs::write(&path, updated_content)?;
-                    }
-                    data
-                }
-                Err(_) => {
-                    // 如果解析失败，尝试作为旧版本数据解析
-                    #[derive(Deserialize)]
-                    struct OldClipboardData {
-                        items: Vec<ClipboardItem>,
-                        next_id: u64,
-                        settings: AppSettings,
-                    }
+pub const MAX_SAVE_RETRIES: u32 = 10;
 
-                    let old_data: OldClipboardData = serde_json::from_str(&content)
-                        .map_err(|e| format!("解析剪切板数据失败: {}", e))?;
+// 保存重试的首次等待时间，之后每次翻倍
+const SAVE_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
 
-                    // 转换为新格式并添加last_updated字段
-                    let new_data = ClipboardData {
-                        items: old_data.items,
-                        next_id: old_data.next_id,
-                        settings: old_data.settings,
-                        last_updated: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)?
-                            .as_secs(),
-                        is_first_launch: false,
-                    };
+// 一次保存中重试等待的总时长上限，同步保存时调用方持有存储锁，不能让界面长时间卡住；
+// 超出后交给后台自动保存继续尝试
+const SAVE_RETRY_MAX_WAIT: Duration = Duration::from_millis(400);
 
-                    // 保存更新后的数据
-                    let updated_content = serde_json::to_string_pretty(&new_data)?;
-                    fs::write(&path, updated_content)?;
+// 文件被其他进程短暂占用等可能自行恢复的错误，值得重试；权限不足重试也不会好转
+fn is_transient_io_error(err: &StorageError) -> bool {
+    use std::io::ErrorKind;
+    let StorageError::Io(e) = err else {
+        return false;
+    };
+    // Windows 上文件被杀毒软件、同步工具占用时报共享冲突或锁冲突
+    #[cfg(target_os = "windows")]
+    if matches!(e.raw_os_error(), Some(32) | Some(33)) {
+        return true;
+    }
+    matches!(e.kind(), ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
 
-                    new_data
-                }
-            }
-        } else {
-            ClipboardData {
-                items: Vec::new(),
-                next_id: 1,
-                settings: AppSettings::default(),
-                last_updated: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)?
-                    .as_secs(),
-                is_first_launch: true,
-            }
-        };
+// 窗口背景不透明度允许的范围
+pub const WINDOW_OPACITY_RANGE: std::ops::RangeInclusive<f64> = 0.2..=1.0;
 
-        Ok(Self {
-            file_path: path,
-            data,
-        })
+impl AppSettings {
+    // 某类内容使用的去重方式
+    pub fn dedup_mode_for(&self, kind: CaptureKind) -> DedupMode {
+        self.dedup_modes.get(&kind).copied().unwrap_or(self.dedup_mode)
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let content = serde_json::to_string_pretty(&self.data)?;
-        fs::write(&self.file_path, content)?;
-        Ok(())
+    // 指定内容类型允许的最大字节数，设置中缺少该类型时使用默认值
+    pub fn limit_for(&self, kind: CaptureKind) -> usize {
+        let kb = self
+            .max_content_kb
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| default_max_content_kb()[&kind]);
+        usize::try_from(kb.saturating_mul(1024)).unwrap_or(usize::MAX)
     }
 
-    pub fn add_item(&mut self, content: String) -> Result<u64, Box<dyn std::error::Error>> {
-        // 检查重复内容
-        if let Some(last_item) = self.data.items.last() {
-            if last_item.content == content {
-                return Ok(last_item.id);
-            }
+    // 校验并规范化用户提交的设置
+    pub fn validate(&mut self) -> Result<(), String> {
+        if !WINDOW_OPACITY_RANGE.contains(&self.window_opacity) {
+            return Err(crate::i18n::tf(
+                "error.invalid_opacity",
+                &[WINDOW_OPACITY_RANGE.start(), WINDOW_OPACITY_RANGE.end()],
+            ));
         }
-
-        // 检查大文本 (>1MB)
-        if content.len() > 1024 * 1024 {
-            return Err("Content too large (>1MB)".into());
+        if self.default_history_limit == 0 || self.default_history_limit > self.max_items {
+            return Err(crate::i18n::tf("error.invalid_history_limit", &[&self.max_items]));
+        }
+        build_capture_filter(&self.capture_only_patterns)
+            .map_err(|e| crate::i18n::tf("error.invalid_capture_pattern", &[&e]))?;
+        if let Some(entry) = self
+            .pipe_command_allowlist
+            .iter()
+            .find(|entry| crate::pipe::AllowedCommand::parse(entry).is_none())
+        {
+            return Err(crate::i18n::tf("error.invalid_pipe_command", &[entry]));
         }
+        // 上限为 0 时任何内容都无法记录，视为未配置
+        self.max_content_kb.retain(|_, kb| *kb > 0);
+        // 总等待时长另有 SAVE_RETRY_MAX_WAIT 限制，这里只收敛明显不合理的取值
+        self.save_retries = self.save_retries.min(MAX_SAVE_RETRIES);
+        // 不支持透明窗口的平台上固定为不透明
+        if !crate::platform::get_platform_adapter().supports_transparency() {
+            self.window_opacity = 1.0;
+        }
+        Ok(())
+    }
+}
 
-        let item = ClipboardItem {
-            id: self.data.next_id,
-            content,
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)?
-                .as_secs(),
-            is_favorite: false,
-        };
+// 按数字逐段比较 x.y.z 形式的版本号，无法解析的部分视为 0
+fn is_newer_version(version: &str, than: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['.', '-', '+'])
+            .take(3)
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    !version.is_empty() && parse(version) > parse(than)
+}
 
-        self.data.items.push(item);
-        self.data.next_id += 1;
+// 将“仅记录”规则编译为正则集合，未配置规则时返回 None
+pub fn build_capture_filter(patterns: &[String]) -> Result<Option<RegexSet>, regex::Error> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    RegexSet::new(patterns).map(Some)
+}
 
-        // 更新最后修改时间
-        self.data.last_updated = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?
-            .as_secs();
+// 历史列表的排序方式，均为最新的在前
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryOrder {
+    #[default]
+    LastUsed,
+    Created,
+}
 
-        // 清理旧项目
-        self.enforce_item_limit()?;
+impl HistoryOrder {
+    fn key(self, item: &ClipboardItem) -> u64 {
+        match self {
+            HistoryOrder::LastUsed => item.last_used_at,
+            HistoryOrder::Created => item.created_at,
+        }
+    }
 
-        self.save()?;
-        Ok(self.data.next_id - 1)
+    // 同一秒内的条目再按 id 降序，保证多次获取顺序一致
+    fn compare(self, a: &ClipboardItem, b: &ClipboardItem) -> std::cmp::Ordering {
+        self.key(b).cmp(&self.key(a)).then_with(|| b.id.cmp(&a.id))
     }
 
-    pub fn get_history(&self, limit: usize) -> Vec<ClipboardItem> {
-        let mut items: Vec<ClipboardItem> = self.data.items.clone();
-        // 按时间戳降序排列（最新的在前）
-        items.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    fn sort(self, items: &mut [ClipboardItem]) {
+        items.sort_by(|a, b| self.compare(a, b));
+    }
+}
 
-        // 限制返回数量
-        items.truncate(limit);
-        items
+// 把连续空白（包括换行）折叠为一个空格，生成单行预览；超长内容只处理开头部分
+fn compact_preview(content: &str) -> String {
+    let mut compact = String::new();
+    for word in content.split_whitespace() {
+        if !compact.is_empty() {
+            compact.push(' ');
+        }
+        compact.push_str(word);
+        // 按字节粗略判断，足够截出 PREVIEW_LINE_CHARS 个字符即可停止
+        if compact.len() > PREVIEW_LINE_CHARS * 4 {
+            break;
+        }
     }
+    truncate_preview_line(&compact)
+}
 
-    pub fn get_all_items(&self) -> Vec<ClipboardItem> {
-        let mut items: Vec<ClipboardItem> = self.data.items.clone();
-        // 按时间戳降序排列（最新的在前）
-        items.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        items
+// 按字符边界截断单行预览，超出部分以省略号表示
+fn truncate_preview_line(line: &str) -> String {
+    match line.char_indices().nth(PREVIEW_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
     }
+}
 
-    pub fn get_item_by_id(&self, id: u64) -> Option<&ClipboardItem> {
-        self.data.items.iter().find(|item| item.id == id)
+// 搜索匹配规则：对内容和备注做不区分大小写的子串匹配，query 需已转为小写
+fn matches_query(item: &ClipboardItem, query: &str) -> bool {
+    query.is_empty()
+        || contains_ignore_case(&item.content, query)
+        || item.note.as_deref().is_some_and(|note| contains_ignore_case(note, query))
+}
+
+// 不分配新字符串的大小写不敏感查找，避免每次搜索都复制一份兆字节级的小写内容
+fn contains_ignore_case(haystack: &str, needle_lower: &str) -> bool {
+    if needle_lower.is_empty() {
+        return true;
+    }
+    if needle_lower.is_ascii() {
+        let needle = needle_lower.as_bytes();
+        return haystack
+            .as_bytes()
+            .windows(needle.len())
+            .any(|window| window.eq_ignore_ascii_case(needle));
     }
 
-    pub fn remove_item(&mut self, id: u64) -> Result<bool, Box<dyn std::error::Error>> {
-        let original_len = self.data.items.len();
-        self.data.items.retain(|item| item.id != id);
-        let removed = self.data.items.len() < original_len;
+    haystack.char_indices().any(|(start, _)| {
+        let mut rest = haystack[start..].chars().flat_map(char::to_lowercase);
+        needle_lower.chars().all(|expected| rest.next() == Some(expected))
+    })
+}
 
-        if removed {
-            self.save()?;
+// 可记录的剪切板内容类型；目前只有文本会被实际捕获，其余类型预留给多格式支持
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureKind {
+    Text,
+    Image,
+    Html,
+    Files,
+}
+
+// 判断两次复制是否为重复内容的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupMode {
+    // 逐字节完全相同
+    #[default]
+    Exact,
+    // 忽略首尾空白，并把连续空白视为一个空格
+    Normalized,
+}
+
+impl DedupMode {
+    pub fn is_duplicate(self, a: &str, b: &str) -> bool {
+        match self {
+            DedupMode::Exact => a == b,
+            DedupMode::Normalized => a.split_whitespace().eq(b.split_whitespace()),
         }
-        Ok(removed)
     }
+}
 
-    pub fn set_item_favorite(&mut self, id: u64, is_favorite: bool) -> Result<bool, Box<dyn std::error::Error>> {
-        if let Some(item) = self.data.items.iter_mut().find(|item| item.id == id) {
-            if item.is_favorite != is_favorite {
-                item.is_favorite = is_favorite;
-                self.data.last_updated = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)?
-                    .as_secs();
-                self.save()?;
+// 用户选择的列表排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Recent,
+    FavoritesFirst,
+    MostUsed,
+    Alphabetical,
+}
+
+impl SortMode {
+    // 同等条件下都以最近使用时间降序兜底
+    fn sort(self, items: &mut [ClipboardItem]) {
+        HistoryOrder::LastUsed.sort(items);
+        match self {
+            SortMode::Recent => {}
+            SortMode::FavoritesFirst => items.sort_by_key(|item| !item.is_favorite),
+            SortMode::MostUsed => items.sort_by(|a, b| b.use_count.cmp(&a.use_count)),
+            SortMode::Alphabetical => {
+                items.sort_by_cached_key(|item| item.content.to_lowercase())
             }
-            return Ok(true);
         }
-        Ok(false)
-    }
-
-    pub fn clear_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.data.items.clear();
-        self.data.next_id = 1;
-        self.save()?;
-        Ok(())
     }
+}
 
-    pub fn search_items(&self, query: &str) -> Vec<ClipboardItem> {
-        let mut items: Vec<ClipboardItem> = if query.is_empty() {
-            self.data.items.clone()
-        } else {
-            self.data.items
-                .iter()
-                .filter(|item| item.content.to_lowercase().contains(&query.to_lowercase()))
-                .cloned()
-                .collect()
-        };
+// 列表渲染用的轻量投影，不含完整内容
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemSummary {
+    pub id: u64,
+    pub preview_lines: Vec<String>,
+    pub line_count: usize,
+    pub timestamp: u64,
+    pub is_favorite: bool,
+    pub is_scratchpad: bool,
+    pub color_label: Option<ItemColor>,
+    pub source: CaptureSource,
+    pub note: Option<String>,
+}
 
-        // 按时间戳降序排列（最新的在前）
-        items.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        items
+impl ItemSummary {
+    // 紧凑样式下以单行紧凑预览代替多行预览
+    pub fn with_style(item: &ClipboardItem, style: PreviewStyle) -> Self {
+        let mut summary = Self::from(item);
+        if style == PreviewStyle::Compact && !item.compact_preview.is_empty() {
+            summary.preview_lines = vec![item.compact_preview.clone()];
+        }
+        summary
     }
+}
 
-    pub fn get_last_updated(&self) -> u64 {
-        self.data.last_updated
+impl From<&ClipboardItem> for ItemSummary {
+    fn from(item: &ClipboardItem) -> Self {
+        Self {
+            id: item.id,
+            preview_lines: item.preview_lines.clone(),
+            line_count: item.line_count,
+            timestamp: item.last_used_at,
+            is_favorite: item.is_favorite,
+            is_scratchpad: item.is_scratchpad,
+            color_label: item.color_label,
+            source: item.source,
+            note: item.note.clone(),
+        }
     }
+}
 
-    pub fn enforce_item_limit(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let max_items = self.data.settings.max_items;
+// 窗口显示时的定位方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowAnchor {
+    #[default]
+    Cursor,
+    TopRight,
+    BottomRight,
+    Center,
+    // 紧贴托盘图标，拿不到图标位置时按光标定位
+    Tray,
+}
 
-        if self.data.items.len() > max_items {
-            let remove_count = self.data.items.len() - max_items;
-            // 保留收藏的项目
-            let mut to_remove = Vec::new();
+// 向焦点输入框发送内容的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteMethod {
+    // 逐字模拟键盘输入，不会覆盖剪切板
+    #[default]
+    Type,
+    // 写入剪切板后模拟 Ctrl/Cmd+V，速度快且兼容输入法
+    Keystroke,
+}
 
-            for (index, item) in self.data.items.iter().enumerate() {
-                if !item.is_favorite && to_remove.len() < remove_count {
-                    to_remove.push(index);
-                }
-            }
+// 窗口显示与隐藏的管理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+    // 弹出面板：失去焦点时自动隐藏
+    #[default]
+    Popup,
+    // 交由窗口管理器（如 i3、sway）管理：失焦从不隐藏，只有快捷键和托盘会显示/隐藏窗口
+    Managed,
+}
 
-            // 从后往前删除，避免索引错位
-            for &index in to_remove.iter().rev() {
-                self.data.items.remove(index);
-            }
-        }
+// 历史列表的预览样式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewStyle {
+    // 保留原有换行与缩进，显示前几行
+    #[default]
+    Lines,
+    // 折叠空白后显示单行
+    Compact,
+}
 
-        Ok(())
-    }
+// 逐字输入遇到非 ASCII 字符时的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TypingLayout {
+    // 含非 ASCII 字符（重音字母、中日韩文字等）时改用剪切板粘贴，粘贴后恢复原剪切板
+    #[default]
+    Auto,
+    // 始终逐字输入，适合能正确处理 Unicode 输入的平台与布局
+    Unicode,
 }
 
-// 类型别名，便于在 Tauri 命令中使用
-pub type SharedStorage = Arc<Mutex<SimpleStorage>>;
+impl Default for AppSettings {
+    fn default() -> Self {
+        // 使用平台适配器获取默认快捷键
+        let adapter = crate::platform::get_platform_adapter();
+        Self {
+            max_items: 100,
+            max_size_mb: 50,
+            auto_start: false,
+            shortcut: adapter.default_shortcut(),
+            window_anchor: WindowAnchor::default(),
+            paste_method: PasteMethod::default(),
+            restore_clipboard_after_paste: false,
+            require_clear_confirmation: true,
+            locale: Locale::detect(),
+            enable_local_api: false,
+            local_api_port: default_local_api_port(),
+            local_api_token: String::new(),
+            window_opacity: default_window_opacity(),
+            fetch_link_titles: false,
+            default_history_limit: default_history_limit(),
+            dedup_window_secs: 0,
+            capture_only_patterns: Vec::new(),
+            capture_primary_selection: false,
+            max_item_age_days: 0,
+            maintenance_idle_secs: default_maintenance_idle_secs(),
+            sort_mode: SortMode::default(),
+            close_after_paste: true,
+            capture_kinds: default_capture_kinds(),
+            typing_layout: TypingLayout::default(),
+            window_mode: WindowMode::default(),
+            dedup_mode: DedupMode::default(),
+            dedup_modes: HashMap::new(),
+            save_retries: default_save_retries(),
+            capture_shortcut: String::new(),
+            pin_to_paste_target: false,
+            capture_custom_formats: Vec::new(),
+            preview_style: PreviewStyle::default(),
+            pipe_command_allowlist: Vec::new(),
+            max_content_kb: default_max_content_kb(),
+            focus_search_on_show: true,
+            history_enabled: true,
+            session_only: false,
+        }
+    }
+}
+
+// 单个条目的内容统计，按需计算，不放入列表数据
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemStats {
+    pub chars: usize,
+    pub bytes: usize,
+    pub words: usize,
+    pub lines: usize,
+}
+
+impl ItemStats {
+    pub fn from_content(content: &str) -> Self {
+        Self {
+            chars: content.chars().count(),
+            bytes: content.len(),
+            words: content.split_whitespace().count(),
+            lines: content.lines().count(),
+        }
+    }
+}
+
+// 增量同步的结果，cursor 作为下次请求的 after_id
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemsSince {
+    pub items: Vec<ClipboardItem>,
+    // 便签不参与 id 游标，每次都随结果返回
+    pub scratchpad: Option<ClipboardItem>,
+    pub cursor: u64,
+}
+
+// 本地使用情况统计，只基于已保存的条目计算
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSummary {
+    // 从最早一天到今天，每天的捕获数
+    pub days: Vec<DayUsage>,
+    // 按本地时间 0-23 点统计的捕获数
+    pub hours: Vec<usize>,
+    // 按来源统计，数量多的在前
+    pub sources: Vec<SourceUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DayUsage {
+    // 当天本地零点对应的 Unix 时间戳
+    pub day_start: u64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceUsage {
+    pub source: CaptureSource,
+    pub count: usize,
+}
+
+// add_item_from 的结果：条目 id，是否用掉了一次性的大小限制豁免，
+// 以及是否与最新条目重复而未做任何改动
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddedItem {
+    pub id: u64,
+    pub oversize: bool,
+    pub duplicate: bool,
+}
+
+// 合并导入历史的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub skipped: usize,
+    // 内容已存在，仅把收藏状态合并到原条目
+    pub favorite_merged: usize,
+}
+
+// 数据文件自检结果
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub issues: Vec<String>,
+    pub repaired: usize,
+}
+
+// 存储状态概览，用于诊断数据文件位置等问题
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStats {
+    pub path: String,
+    pub used_fallback: bool,
+    pub item_count: usize,
+    // 启动时读到的数据文件写入版本，旧数据为空
+    pub last_written_by_version: String,
+    pub app_version: String,
+    // 最近一次保存是否成功，为 false 时内存中的修改尚未写入磁盘
+    pub save_healthy: bool,
+    // 仅保存在内存中的会话条目数，关闭仅会话模式前据此询问是否转存
+    pub session_item_count: usize,
+}
+
+// 落盘状态，与存储数据分开共享，使序列化与写文件可以在存储锁之外进行
+#[derive(Default)]
+struct SaveState {
+    // 是否有尚未写入磁盘的修改
+    dirty: AtomicBool,
+    // 连续保存失败的次数
+    failures: AtomicU32,
+    // 每次修改递增，用于识别过期的快照
+    generation: AtomicU64,
+    // 已写入磁盘的版本；写文件期间持有，保证同一时间只有一个写入者
+    written: Mutex<u64>,
+}
+
+impl SaveState {
+    fn lock_written(&self) -> MutexGuard<'_, u64> {
+        self.written.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // 写入 generation 版本的数据；更新的版本已写入时直接跳过。
+    // 重试等待期间释放写入锁，其他保存和退出前的落盘不必排队等待
+    fn write(&self, path: &Path, data: &ClipboardData, generation: u64, retries: u32) -> StorageResult<()> {
+        let mut delay = SAVE_RETRY_BASE_DELAY;
+        let mut waited = Duration::ZERO;
+        let mut attempt = 0;
+        loop {
+            let mut written = self.lock_written();
+            if *written >= generation {
+                return Ok(());
+            }
+
+            match write_data_file(path, data) {
+                Ok(()) => {
+                    *written = generation;
+                    self.failures.store(0, Ordering::SeqCst);
+                    return Ok(());
+                }
+                Err(e) if attempt < retries && waited + delay <= SAVE_RETRY_MAX_WAIT && is_transient_io_error(&e) => {
+                    drop(written);
+                    eprintln!("保存剪切板数据失败，{} 毫秒后重试: {}", delay.as_millis(), e);
+                    std::thread::sleep(delay);
+                    waited += delay;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    // 保持脏标记，由后台任务继续重试
+                    self.dirty.store(true, Ordering::SeqCst);
+                    self.failures.fetch_add(1, Ordering::SeqCst);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+fn write_data_file(path: &Path, data: &ClipboardData) -> StorageResult<()> {
+    let content = serde_json::to_string_pretty(data)?;
+    // 先写临时文件再替换，避免写入中途退出导致数据文件损坏
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// 某一时刻的数据副本，可在释放存储锁后写入磁盘
+pub struct SaveSnapshot {
+    state: Arc<SaveState>,
+    path: PathBuf,
+    data: ClipboardData,
+    generation: u64,
+    retries: u32,
+}
+
+impl SaveSnapshot {
+    pub fn write(self) -> StorageResult<()> {
+        self.state.write(&self.path, &self.data, self.generation, self.retries)
+    }
+}
+
+pub struct SimpleStorage {
+    file_path: PathBuf,
+    // 是否退回到了当前工作目录下的 .clipper
+    used_fallback: bool,
+    // 由 capture_only_patterns 编译而来，设置变更时需刷新
+    capture_filter: Option<RegexSet>,
+    // 最近删除的条目（最新的在队尾），仅保存在内存中，用于撤销误删
+    recently_deleted: VecDeque<ClipboardItem>,
+    // 仅会话模式下捕获的条目，不属于 data，保存时不会写入。与持久条目一样可以查找、搜索、
+    // 收藏和编辑（经由 history_items、history_item_mut），但导出时不包含
+    session_items: Vec<ClipboardItem>,
+    // 按排序方式缓存的列表投影，数据变更时清空
+    summary_cache: Mutex<HashMap<HistoryOrder, Vec<ItemSummary>>>,
+    // 落盘状态，后台保存时在存储锁之外使用
+    save_state: Arc<SaveState>,
+    // 最近一次捕获与最近一次维护的时间，用于判断空闲维护时机
+    last_capture_at: Instant,
+    last_maintenance_at: Instant,
+    // 一次性放宽大小限制，下一次捕获后自动复位
+    oversize_once: bool,
+    // 历史每次变化都递增，只保存在内存中，供前端轮询判断是否需要重新拉取
+    revision: u64,
+    // 启动时数据文件记录的写入版本
+    loaded_version: String,
+    pub data: ClipboardData,
+}
+
+impl SimpleStorage {
+    // 返回数据文件路径，以及是否使用了当前工作目录作为后备位置
+    pub fn resolve_storage_path() -> StorageResult<(PathBuf, bool)> {
+        let (base, used_fallback) = Self::default_data_dir()?;
+
+        // 数据文件迁移过时，默认目录中记录了新的位置
+        if let Ok(location) = fs::read_to_string(base.join(LOCATION_FILE_NAME)) {
+            let dir = PathBuf::from(location.trim());
+            if dir.is_dir() {
+                return Ok((dir.join(DATA_FILE_NAME), false));
+            }
+            eprintln!("记录的数据目录不存在，改用默认位置: {}", dir.display());
+        }
+
+        Ok((base.join(DATA_FILE_NAME), used_fallback))
+    }
+
+    // 默认数据目录，以及是否退回到了当前工作目录
+    fn default_data_dir() -> StorageResult<(PathBuf, bool)> {
+        let mut candidates = Vec::new();
+        candidates.push(data_local_dir());
+        candidates.push(data_dir());
+        candidates.push(config_dir());
+
+        for candidate in candidates.into_iter().flatten() {
+            let mut base = candidate.clone();
+            base.push("clipper");
+            if fs::create_dir_all(&base).is_ok() {
+                return Ok((base, false));
+            }
+        }
+
+        let mut fallback = std::env::current_dir()?;
+        fallback.push(".clipper");
+        fs::create_dir_all(&fallback)?;
+        eprintln!(
+            "警告：无法使用系统数据目录，剪切板数据将保存在当前工作目录: {}",
+            fallback.display()
+        );
+        Ok((fallback, true))
+    }
+
+    // 将数据文件迁移到新目录：先完整写入并校验新文件，再切换路径，最后按需删除旧文件
+    pub fn relocate(&mut self, new_dir: &Path, remove_old: bool) -> StorageResult<PathBuf> {
+        // 迁移期间不允许后台写入旧位置
+        let save_state = Arc::clone(&self.save_state);
+        let mut written = save_state.lock_written();
+        fs::create_dir_all(new_dir)?;
+        let new_path = new_dir.join(DATA_FILE_NAME);
+        if new_path == self.file_path {
+            return Ok(new_path);
+        }
+        if new_path.exists() {
+            return Err(StorageError::AlreadyExists(new_path.display().to_string()));
+        }
+
+        let content = serde_json::to_string_pretty(&self.data)?;
+        let tmp_path = new_path.with_extension("json.tmp");
+        fs::write(&tmp_path, &content)?;
+        fs::rename(&tmp_path, &new_path)?;
+
+        // 读回校验，确认新文件可以正常解析
+        let written = fs::read_to_string(&new_path)?;
+        if let Err(e) = serde_json::from_str::<ClipboardData>(&written) {
+            let _ = fs::remove_file(&new_path);
+            return Err(e.into());
+        }
+
+        // 在默认目录记录新位置，迁回默认目录时删除记录
+        let (base, _) = Self::default_data_dir()?;
+        let location_file = base.join(LOCATION_FILE_NAME);
+        if new_dir == base {
+            if location_file.exists() {
+                fs::remove_file(&location_file)?;
+            }
+        } else {
+            fs::write(&location_file, new_dir.display().to_string())?;
+        }
+
+        // 新文件已包含当前全部数据，之前的快照都不必再写
+        *written = save_state.generation.load(Ordering::SeqCst);
+        let old_path = std::mem::replace(&mut self.file_path, new_path.clone());
+        self.used_fallback = false;
+
+        if remove_old {
+            if let Err(e) = fs::remove_file(&old_path) {
+                eprintln!("删除旧数据文件失败: {}", e);
+            }
+        }
+        Ok(new_path)
+    }
+
+    pub fn new() -> StorageResult<Self> {
+        let (path, used_fallback) = Self::resolve_storage_path()?;
+
+        if !path.exists() {
+            let mut legacy = std::env::current_dir()?;
+            legacy.push("clipboard_data.json");
+            if legacy.exists() {
+                if let Err(err) = fs::copy(&legacy, &path) {
+                    eprintln!("迁移旧版剪切板数据失败: {}", err);
+                }
+            }
+        }
+
+        Self::load(path, used_fallback)
+    }
+
+    // 读取指定的数据文件，不存在时使用首次启动的空数据
+    fn load(path: PathBuf, used_fallback: bool) -> StorageResult<Self> {
+        let mut data = if path.exists() {
+            let content = fs::read_to_string(&path)?;
+
+            // 首先尝试解析为完整结构
+            match serde_json::from_str::<ClipboardData>(&content) {
+                Ok(mut data) => {
+                    // 如果成功解析但没有last_updated字段，添加当前时间
+                    if data.last_updated == 0 {
+                        data.last_updated = now_secs();
+                        // 立即保存更新的数据
+                        let updated_content = serde_json::to_string_pretty(&data)?;
+                        fs::write(&path, updated_content)?;
+                    }
+                    data
+                }
+                Err(_) => {
+                    // 如果解析失败，尝试作为旧版本数据解析
+                    #[derive(Deserialize)]
+                    struct OldClipboardData {
+                        items: Vec<ClipboardItem>,
+                        next_id: u64,
+                        settings: AppSettings,
+                    }
+
+                    let old_data: OldClipboardData = serde_json::from_str(&content)?;
+
+                    // 转换为新格式并添加last_updated字段
+                    let new_data = ClipboardData {
+                        items: old_data.items,
+                        next_id: old_data.next_id,
+                        settings: old_data.settings,
+                        last_updated: now_secs(),
+                        is_first_launch: false,
+                        scratchpad: None,
+                        collections: Vec::new(),
+                        schema_version: 0,
+                        last_written_by_version: String::new(),
+                    };
+
+                    // 保存更新后的数据
+                    let updated_content = serde_json::to_string_pretty(&new_data)?;
+                    fs::write(&path, updated_content)?;
+
+                    new_data
+                }
+            }
+        } else {
+            ClipboardData::first_launch()
+        };
+
+        let (loaded_version, migrated) = Self::prepare_loaded(&mut data);
+
+        let mut storage = Self::from_parts(path, used_fallback, loaded_version, data);
+        if migrated {
+            storage.save()?;
+        }
+        Ok(storage)
+    }
+
+    fn from_parts(file_path: PathBuf, used_fallback: bool, loaded_version: String, data: ClipboardData) -> Self {
+        let mut storage = Self {
+            file_path,
+            used_fallback,
+            capture_filter: None,
+            recently_deleted: VecDeque::new(),
+            session_items: Vec::new(),
+            summary_cache: Mutex::new(HashMap::new()),
+            save_state: Arc::new(SaveState::default()),
+            last_capture_at: Instant::now(),
+            last_maintenance_at: Instant::now(),
+            oversize_once: false,
+            revision: 0,
+            loaded_version,
+            data,
+        };
+        storage.refresh_capture_filter();
+        storage
+    }
+
+    // 整理刚读入的数据：记录写入它的版本、按需迁移并收敛设置，返回原写入版本与是否执行了迁移
+    fn prepare_loaded(data: &mut ClipboardData) -> (String, bool) {
+        // 由更新版本写入的数据可能含有本版本不认识的内容，不做任何迁移
+        let loaded_version = std::mem::replace(&mut data.last_written_by_version, APP_VERSION.to_string());
+        let written_by_newer = is_newer_version(&loaded_version, APP_VERSION) || data.schema_version > SCHEMA_VERSION;
+        if written_by_newer {
+            eprintln!(
+                "警告：数据文件由更新的版本 {}（格式版本 {}）写入，当前版本为 {}，已跳过数据迁移；降级使用可能丢失新版本的数据",
+                loaded_version, data.schema_version, APP_VERSION
+            );
+        }
+        let migrated = !written_by_newer && Self::migrate(data);
+
+        // 旧数据的上限可能小于默认显示条数，加载时收敛到合法范围
+        let settings = &mut data.settings;
+        settings.default_history_limit = settings.default_history_limit.clamp(1, settings.max_items.max(1));
+
+        (loaded_version, migrated)
+    }
+
+    // 当前数据序列化后的 JSON，格式与数据文件一致；由内存数据生成，不会读到写了一半的文件
+    pub fn to_raw_json(&self) -> StorageResult<String> {
+        Ok(serde_json::to_string_pretty(&self.data)?)
+    }
+
+    // 用备份工具提供的 JSON 整体替换数据并立即保存；解析失败或设置未通过校验时保持当前数据不变
+    pub fn replace_from_raw_json(&mut self, json: &str) -> StorageResult<()> {
+        let mut data: ClipboardData = serde_json::from_str(json)?;
+        // 与 update_settings 相同的校验与规范化，避免越界取值绕过设置页进入数据
+        data.settings.validate().map_err(StorageError::InvalidSettings)?;
+        let (loaded_version, _) = Self::prepare_loaded(&mut data);
+
+        self.data = data;
+        self.loaded_version = loaded_version;
+        self.refresh_capture_filter();
+        // 备份中的修改时间可能早于当前数据，刷新后轮询方才能发现变化
+        self.mark_updated();
+        // 保存时递增写入代数，替换前排队的旧快照不会再覆盖新数据
+        self.save()
+    }
+
+    // 重新从磁盘读取数据文件（如被手动编辑或同步工具覆盖），内存中尚未保存的修改会被丢弃；
+    // 读取或解析失败时保持当前数据不变
+    pub fn reload(&mut self) -> StorageResult<()> {
+        if self.save_state.dirty.load(Ordering::SeqCst) {
+            eprintln!("重新加载数据文件，未保存的修改将被丢弃");
+        }
+        // 作废所有尚未写入的快照，避免读取后又被旧数据覆盖
+        let mut written = self.save_state.lock_written();
+        let generation = self.save_state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let previous = std::mem::replace(&mut *written, generation);
+        let mut fresh = match Self::load(self.file_path.clone(), self.used_fallback) {
+            Ok(fresh) => fresh,
+            Err(e) => {
+                // 读取失败时内存数据保持不变，恢复原状态以便继续保存
+                *written = previous;
+                return Err(e);
+            }
+        };
+        drop(written);
+
+        self.save_state.dirty.store(false, Ordering::SeqCst);
+        fresh.save_state = Arc::clone(&self.save_state);
+        // 撤销队列和会话条目只在内存中，保留以便继续使用
+        fresh.recently_deleted = std::mem::take(&mut self.recently_deleted);
+        fresh.session_items = std::mem::take(&mut self.session_items);
+        // 重新读取后内容可能不同，revision 继续递增而不是归零，轮询方才能发现变化
+        fresh.revision = self.revision + 1;
+        *self = fresh;
+        Ok(())
+    }
+
+    // 按 schema_version 逐级补齐旧数据，返回是否执行了迁移
+    fn migrate(data: &mut ClipboardData) -> bool {
+        if data.schema_version >= SCHEMA_VERSION {
+            return false;
+        }
+
+        if data.schema_version < 1 {
+            // 旧版本数据没有行数和预览信息，补齐后新界面才能一致显示
+            for item in data.items.iter_mut().chain(data.scratchpad.iter_mut()) {
+                item.refresh_metadata();
+                // 旧数据只有一个时间戳，作为首次捕获时间
+                if item.created_at == 0 {
+                    item.created_at = item.last_used_at;
+                }
+            }
+        }
+
+        if data.schema_version < 2 {
+            // 预览改为按字符数截断，重新生成旧条目的超长预览
+            for item in data.items.iter_mut().chain(data.scratchpad.iter_mut()) {
+                item.refresh_metadata();
+            }
+        }
+
+        if data.schema_version < 3 {
+            // 新增单行紧凑预览，为旧条目补齐
+            for item in data.items.iter_mut().chain(data.scratchpad.iter_mut()) {
+                item.refresh_metadata();
+            }
+        }
+
+        dev_log!("数据格式已从版本 {} 迁移到 {}", data.schema_version, SCHEMA_VERSION);
+        data.schema_version = SCHEMA_VERSION;
+        true
+    }
+
+    // 按当前设置重新编译捕获规则；规则无效时不过滤任何内容
+    pub fn refresh_capture_filter(&mut self) {
+        self.capture_filter = match build_capture_filter(&self.data.settings.capture_only_patterns) {
+            Ok(filter) => filter,
+            Err(e) => {
+                eprintln!("仅记录规则无效，已忽略: {}", e);
+                None
+            }
+        };
+    }
+
+    // 监控到的新文本是否应当记录：需启用文本类型且匹配仅记录规则
+    pub fn should_capture(&self, content: &str) -> bool {
+        if !self.data.settings.capture_kinds.contains(&CaptureKind::Text) {
+            return false;
+        }
+        self.capture_filter
+            .as_ref()
+            .map_or(true, |filter| filter.is_match(content))
+    }
+
+    // 统计最近 days 天（含今天）的捕获情况；utc_offset_secs 为本地时区相对 UTC 的偏移
+    pub fn usage_summary(&self, days: usize, utc_offset_secs: i64) -> StorageResult<UsageSummary> {
+        const DAY_SECS: i64 = 86_400;
+        // 最多统计一年，避免传入过大的天数时分配巨大的数组
+        const MAX_DAYS: usize = 366;
+
+        let days = days.clamp(1, MAX_DAYS);
+        let now = now_secs() as i64;
+        let today = (now + utc_offset_secs).div_euclid(DAY_SECS);
+        let first_day = today - (days as i64 - 1);
+
+        let mut day_counts = vec![0usize; days];
+        let mut hours = vec![0usize; 24];
+        let mut sources: Vec<SourceUsage> = Vec::new();
+
+        for item in &self.data.items {
+            let local = item.created_at as i64 + utc_offset_secs;
+            let day = local.div_euclid(DAY_SECS);
+            if day < first_day || day > today {
+                continue;
+            }
+
+            day_counts[(day - first_day) as usize] += 1;
+            hours[(local.rem_euclid(DAY_SECS) / 3_600) as usize] += 1;
+            match sources.iter_mut().find(|usage| usage.source == item.source) {
+                Some(usage) => usage.count += 1,
+                None => sources.push(SourceUsage {
+                    source: item.source,
+                    count: 1,
+                }),
+            }
+        }
+
+        sources.sort_by(|a, b| b.count.cmp(&a.count));
+        let days = day_counts
+            .into_iter()
+            .enumerate()
+            .map(|(offset, count)| DayUsage {
+                day_start: ((first_day + offset as i64) * DAY_SECS - utc_offset_secs).max(0) as u64,
+                count,
+            })
+            .collect();
+
+        Ok(UsageSummary {
+            days,
+            hours,
+            sources,
+        })
+    }
+
+    pub fn stats(&self) -> StorageStats {
+        StorageStats {
+            path: self.file_path.display().to_string(),
+            used_fallback: self.used_fallback,
+            item_count: self.data.items.len(),
+            last_written_by_version: self.loaded_version.clone(),
+            app_version: APP_VERSION.to_string(),
+            save_healthy: self.save_healthy(),
+            session_item_count: self.session_item_count(),
+        }
+    }
+
+    // 立即写入磁盘；遇到暂时性错误时按设置重试，仍失败则记入连续失败次数
+    pub fn save(&self) -> StorageResult<()> {
+        self.clear_summary_cache();
+        // 直接修改设置后调用 save 时没有经过 mark_dirty，这里单独递增版本
+        let generation = self.save_state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.save_state.dirty.store(false, Ordering::SeqCst);
+        self.save_state
+            .write(&self.file_path, &self.data, generation, self.data.settings.save_retries)
+    }
+
+    // 有未保存的修改时克隆一份快照并清除脏标记，之后可在存储锁之外序列化和写文件，
+    // 避免大文件写入期间阻塞读取历史的命令
+    pub fn snapshot(&self) -> Option<SaveSnapshot> {
+        if !self.save_state.dirty.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+        Some(SaveSnapshot {
+            state: Arc::clone(&self.save_state),
+            path: self.file_path.clone(),
+            data: self.data.clone(),
+            generation: self.save_state.generation.load(Ordering::SeqCst),
+            retries: self.data.settings.save_retries,
+        })
+    }
+
+    // 连续保存失败的次数，成功一次即清零
+    pub fn save_failures(&self) -> u32 {
+        self.save_state.failures.load(Ordering::SeqCst)
+    }
+
+    pub fn save_healthy(&self) -> bool {
+        self.save_failures() == 0
+    }
+
+    fn clear_summary_cache(&self) {
+        self.summary_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+
+    // 标记数据已修改，由后台任务合并写入，避免每次复制都同步写整个文件
+    pub fn mark_dirty(&self) {
+        self.clear_summary_cache();
+        self.save_state.generation.fetch_add(1, Ordering::SeqCst);
+        self.save_state.dirty.store(true, Ordering::SeqCst);
+    }
+
+    // 历史内容发生变化：记录修改时间、递增 revision 并标记需要保存，所有修改历史的操作都应经由此处。
+    // last_updated 保持为真实时间；同一秒内的多次修改靠 revision 区分
+    pub fn mark_updated(&mut self) {
+        self.data.last_updated = now_secs();
+        self.revision += 1;
+        self.mark_dirty();
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // 有未保存的修改时立即写入，退出前调用
+    pub fn flush(&self) -> StorageResult<()> {
+        if self.save_state.dirty.load(Ordering::SeqCst) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    // 检查数据文件中的异常状态，repair 为 true 时就地修复并保存
+    pub fn verify(&mut self, repair: bool) -> StorageResult<StorageReport> {
+        let mut issues = Vec::new();
+        let mut repaired = 0;
+        let now = now_secs();
+
+        // 空内容的条目
+        let empty_count = self.data.items.iter().filter(|item| item.content.is_empty()).count();
+        for item in self.data.items.iter().filter(|item| item.content.is_empty()) {
+            issues.push(tf("verify.empty_item", &[&item.id]));
+        }
+        if repair && empty_count > 0 {
+            self.data.items.retain(|item| !item.content.is_empty());
+            repaired += empty_count;
+        }
+
+        // 重复 id（包括与便签保留 id 冲突），修复时重新分配新 id
+        let max_id = self.data.items.iter().map(|item| item.id).max().unwrap_or(0);
+        let mut next_free = self.data.next_id.max(max_id + 1);
+        let mut seen = HashSet::from([SCRATCHPAD_ID]);
+        for item in self.data.items.iter_mut() {
+            if !seen.insert(item.id) {
+                issues.push(tf("verify.duplicate_id", &[&item.id]));
+                if repair {
+                    item.id = next_free;
+                    next_free += 1;
+                    repaired += 1;
+                }
+            }
+        }
+
+        // next_id 必须大于所有已有 id，否则新条目会与旧条目冲突
+        if self.data.next_id <= max_id {
+            issues.push(tf("verify.next_id", &[&self.data.next_id, &max_id]));
+            if repair {
+                repaired += 1;
+            }
+        }
+        if repair {
+            self.data.next_id = next_free;
+        }
+
+        // 时间戳为 0 或明显位于未来（超过一天）的条目
+        for item in self.data.items.iter_mut() {
+            if item.last_used_at == 0 || item.last_used_at > now + 86_400 {
+                issues.push(tf("verify.bad_timestamp", &[&item.id, &item.last_used_at]));
+                if repair {
+                    item.last_used_at = now;
+                    item.created_at = item.created_at.min(now);
+                    repaired += 1;
+                }
+            }
+        }
+
+        // 收藏数量超过上限时无法自动处理，只做提示
+        let favorite_count = self.data.items.iter().filter(|item| item.is_favorite).count();
+        if favorite_count > self.data.settings.max_items {
+            issues.push(tf(
+                "verify.too_many_favorites",
+                &[&favorite_count, &self.data.settings.max_items],
+            ));
+        }
+
+        if repaired > 0 {
+            self.prune_collections();
+            self.mark_updated();
+            self.save()?;
+        }
+
+        Ok(StorageReport { issues, repaired })
+    }
+
+    pub fn add_item(&mut self, content: String) -> StorageResult<u64> {
+        self.add_item_from(content, CaptureSource::Clipboard).map(|added| added.id)
+    }
+
+    // 新增条目并记录来源；命中去重时沿用原条目的来源
+    pub fn add_item_from(&mut self, content: String, source: CaptureSource) -> StorageResult<AddedItem> {
+        self.last_capture_at = Instant::now();
+        // 目前只会捕获文本
+        let dedup = self.data.settings.dedup_mode_for(CaptureKind::Text);
+
+        // 检查重复内容：直接复用已有项目，收藏状态随之保留
+        if let Some(last_item) = self.latest_item() {
+            if dedup.is_duplicate(&last_item.content, &content) {
+                return Ok(AddedItem { id: last_item.id, oversize: false, duplicate: true });
+            }
+        }
+
+        // 检查大文本（上限见 max_content_kb），用户可为下一次复制临时放开限制；
+        // 豁免只在真正放行超限内容时用掉，之前的普通复制不会消耗它
+        let oversize = content.len() > self.data.settings.limit_for(CaptureKind::Text);
+        if oversize {
+            if !self.oversize_once {
+                return Err(StorageError::TooLarge);
+            }
+            self.oversize_once = false;
+        }
+
+        let now = now_secs();
+        let session_only = self.data.settings.session_only;
+
+        // 时间窗口内复制过相同内容：刷新原条目并移到最新位置，而不是新增。
+        // 仅会话模式下只在会话条目中查找，不改动持久条目的使用记录
+        let window = self.data.settings.dedup_window_secs;
+        if window > 0 {
+            let items = if session_only {
+                &mut self.session_items
+            } else {
+                &mut self.data.items
+            };
+            let recent = items.iter().rposition(|item| {
+                dedup.is_duplicate(&item.content, &content) && now.saturating_sub(item.last_used_at) <= window
+            });
+            if let Some(index) = recent {
+                let mut item = items.remove(index);
+                item.touch(now);
+                let id = item.id;
+                items.push(item);
+                self.mark_updated();
+                return Ok(AddedItem { id, oversize, duplicate: false });
+            }
+        }
+
+        let mut item = ClipboardItem::new(self.data.next_id, content, now);
+        item.source = source;
+        let id = item.id;
+        self.insert_new_item(item);
+        Ok(AddedItem { id, oversize, duplicate: false })
+    }
+
+    // 写入新条目：仅会话模式下只保存在内存中，与持久条目共用 id 序列
+    fn insert_new_item(&mut self, item: ClipboardItem) {
+        let id = item.id;
+        self.data.next_id = self.data.next_id.max(id + 1);
+        let max_items = self.data.settings.max_items;
+
+        // 超出条数上限时立即淘汰最旧的非收藏条目，新条目本身不会被淘汰；
+        // 保存和展示的条数始终不超过上限，按保留天数清理等较重的维护仍交给空闲调度
+        if self.data.settings.session_only {
+            self.session_items.push(item);
+            if self.session_items.len() > max_items {
+                let evicted: HashSet<u64> = compute_evictions(&self.session_items, max_items, &HashSet::from([id]))
+                    .into_iter()
+                    .collect();
+                self.session_items.retain(|item| !evicted.contains(&item.id));
+            }
+        } else {
+            self.data.items.push(item);
+            self.evict_over_limit(Some(id));
+        }
+
+        self.mark_updated();
+    }
+
+    // 切换仅会话模式；关闭时 promote 为 true 则把会话条目转为持久条目，否则丢弃，返回转存条数
+    pub fn set_session_only(&mut self, enabled: bool, promote: bool) -> StorageResult<usize> {
+        self.data.settings.session_only = enabled;
+        let mut promoted = 0;
+        if !enabled {
+            let session_items = std::mem::take(&mut self.session_items);
+            if promote {
+                promoted = session_items.len();
+                // 会话条目都晚于持久条目捕获，直接追加即可保持时间顺序
+                self.data.items.extend(session_items);
+                self.enforce_item_limit()?;
+            }
+        }
+        self.mark_updated();
+        self.save()?;
+        Ok(promoted)
+    }
+
+    // 当前仅保存在内存中的会话条目数
+    pub fn session_item_count(&self) -> usize {
+        self.session_items.len()
+    }
+
+    // 手动添加片段：不与最近条目去重，也不写入系统剪切板；与捕获一样遵循仅会话模式与条数上限
+    pub fn add_snippet(
+        &mut self,
+        content: String,
+        favorite: bool,
+        tags: Vec<String>,
+    ) -> StorageResult<ClipboardItem> {
+        if content.len() > self.data.settings.limit_for(CaptureKind::Text) {
+            return Err(StorageError::TooLarge);
+        }
+
+        let now = now_secs();
+
+        let mut item = ClipboardItem::new(self.data.next_id, content, now);
+        item.source = CaptureSource::Snippet;
+        item.is_favorite = favorite;
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !item.tags.iter().any(|existing| existing == tag) {
+                item.tags.push(tag.to_string());
+            }
+        }
+
+        self.insert_new_item(item.clone());
+        Ok(item)
+    }
+
+    // 批量添加片段，超出大小上限而未能添加的计入 skipped
+    pub fn import_snippets(&mut self, chunks: Vec<String>, favorite: bool) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for chunk in chunks {
+            match self.add_snippet(chunk, favorite, Vec::new()) {
+                Ok(_) => summary.added += 1,
+                Err(e) => {
+                    eprintln!("导入片段失败，已跳过: {}", e);
+                    summary.skipped += 1;
+                }
+            }
+        }
+        summary
+    }
+
+    pub fn get_history(&self, limit: usize, order: HistoryOrder) -> Vec<ClipboardItem> {
+        let mut items: Vec<ClipboardItem> = self.history_items().cloned().collect();
+        order.sort(&mut items);
+
+        // 限制返回数量；便签额外放在最前，不占用条数
+        items.truncate(limit);
+        self.prepend_scratchpad(&mut items);
+        items
+    }
+
+    // 列表投影，重复获取时直接复用缓存，避免克隆完整内容
+    pub fn get_history_summaries(&self, limit: usize, order: HistoryOrder) -> Vec<ItemSummary> {
+        let style = self.data.settings.preview_style;
+        let mut cache = self.summary_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let summaries = cache.entry(order).or_insert_with(|| {
+            let mut items: Vec<&ClipboardItem> = self.history_items().collect();
+            items.sort_by(|a, b| order.compare(a, b));
+            self.data
+                .scratchpad
+                .iter()
+                .chain(items)
+                .map(|item| ItemSummary::with_style(item, style))
+                .collect()
+        });
+        // 便签不占用条数
+        let scratchpad = usize::from(self.data.scratchpad.is_some());
+        summaries.iter().take(limit.saturating_add(scratchpad)).cloned().collect()
+    }
+
+    // 按用户设置的排序方式分页获取，便签排在第一页最前
+    pub fn get_sorted_history(&self, limit: usize, offset: usize) -> Vec<ClipboardItem> {
+        let mut items: Vec<ClipboardItem> = self.history_items().cloned().collect();
+        self.data.settings.sort_mode.sort(&mut items);
+        let mut page: Vec<ClipboardItem> = items.into_iter().skip(offset).take(limit).collect();
+        // 便签只出现在第一页，不占用分页条数
+        if offset == 0 {
+            self.prepend_scratchpad(&mut page);
+        }
+        page
+    }
+
+    pub fn get_all_items(&self, order: HistoryOrder) -> Vec<ClipboardItem> {
+        let mut items: Vec<ClipboardItem> = self.history_items().cloned().collect();
+        order.sort(&mut items);
+        self.prepend_scratchpad(&mut items);
+        items
+    }
+
+    // 最近一次捕获的项目（不含便签），用于去重
+    pub fn latest_item(&self) -> Option<&ClipboardItem> {
+        self.session_items.last().or_else(|| self.data.items.last())
+    }
+
+    pub fn get_item_by_id(&self, id: u64) -> Option<&ClipboardItem> {
+        if id == SCRATCHPAD_ID {
+            return self.data.scratchpad.as_ref();
+        }
+        self.history_items().find(|item| item.id == id)
+    }
+
+    // 持久条目与仅会话条目（不含便签），按 id 查找、搜索等都应经由此处，避免漏掉会话条目
+    fn history_items(&self) -> impl Iterator<Item = &ClipboardItem> {
+        self.data.items.iter().chain(&self.session_items)
+    }
+
+    fn history_item_mut(&mut self, id: u64) -> Option<&mut ClipboardItem> {
+        self.data
+            .items
+            .iter_mut()
+            .chain(self.session_items.iter_mut())
+            .find(|item| item.id == id)
+    }
+
+    // 按给定顺序批量获取条目，不存在的 id 直接跳过；只遍历一次历史
+    pub fn get_items_by_ids(&self, ids: &[u64]) -> Vec<ClipboardItem> {
+        let wanted: HashSet<u64> = ids.iter().copied().collect();
+        let found: HashMap<u64, &ClipboardItem> = self
+            .history_items()
+            .chain(self.data.scratchpad.iter())
+            .filter(|item| wanted.contains(&item.id))
+            .map(|item| (item.id, item))
+            .collect();
+        ids.iter().filter_map(|id| found.get(id).map(|item| (*item).clone())).collect()
+    }
+
+    // 按内容查找条目，优先最近捕获的；历史中没有时再看便签
+    pub fn find_by_content(&self, content: &str) -> Option<&ClipboardItem> {
+        // 会话条目都晚于持久条目捕获，先查
+        self.session_items
+            .iter()
+            .rev()
+            .chain(self.data.items.iter().rev())
+            .chain(self.data.scratchpad.iter())
+            .find(|item| item.content == content)
+    }
+
+    pub fn get_scratchpad(&self) -> Option<&ClipboardItem> {
+        self.data.scratchpad.as_ref()
+    }
+
+    pub fn set_scratchpad(&mut self, content: String) -> StorageResult<ClipboardItem> {
+        let now = now_secs();
+        let mut scratchpad = ClipboardItem::new(SCRATCHPAD_ID, content, now);
+        scratchpad.is_scratchpad = true;
+
+        self.data.scratchpad = Some(scratchpad.clone());
+        self.mark_updated();
+        Ok(scratchpad)
+    }
+
+    // 便签始终排在列表最前
+    fn prepend_scratchpad(&self, items: &mut Vec<ClipboardItem>) {
+        if let Some(scratchpad) = &self.data.scratchpad {
+            items.insert(0, scratchpad.clone());
+        }
+    }
+
+    pub fn remove_item(&mut self, id: u64) -> StorageResult<bool> {
+        if let Some(index) = self.session_items.iter().position(|item| item.id == id) {
+            self.session_items.remove(index);
+            self.mark_updated();
+            return Ok(true);
+        }
+
+        let position = self.data.items.iter().position(|item| item.id == id);
+        let removed = position.is_some();
+
+        if let Some(index) = position {
+            let item = self.data.items.remove(index);
+            if self.recently_deleted.len() >= RECENTLY_DELETED_LIMIT {
+                self.recently_deleted.pop_front();
+            }
+            self.recently_deleted.push_back(item);
+            self.prune_collections();
+            self.mark_updated();
+        }
+        Ok(removed)
+    }
+
+    // 最近删除的条目，最新删除的在前
+    pub fn get_recently_deleted(&self) -> Vec<ClipboardItem> {
+        self.recently_deleted.iter().rev().cloned().collect()
+    }
+
+    // 恢复最近一次删除的条目：分配新 id，保留收藏等状态，并按时间放回原位置
+    pub fn undo_last_delete(&mut self) -> StorageResult<Option<ClipboardItem>> {
+        let Some(mut item) = self.recently_deleted.pop_back() else {
+            return Ok(None);
+        };
+        item.id = self.data.next_id;
+        self.data.next_id += 1;
+
+        let index = self
+            .data
+            .items
+            .iter()
+            .position(|existing| existing.last_used_at > item.last_used_at)
+            .unwrap_or(self.data.items.len());
+        self.data.items.insert(index, item.clone());
+
+        // 恢复的条目按时间可能排在最旧的位置，超出上限时不能把它本身淘汰掉
+        self.evict_over_limit(Some(item.id));
+        self.mark_updated();
+        Ok(Some(item))
+    }
+
+    pub fn set_item_favorite(&mut self, id: u64, is_favorite: bool) -> StorageResult<bool> {
+        if let Some(item) = self.history_item_mut(id) {
+            if item.is_favorite != is_favorite {
+                item.is_favorite = is_favorite;
+                self.mark_updated();
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // 批量设置收藏状态，只记一次修改；返回状态实际发生变化的条目 id
+    pub fn set_items_favorite(&mut self, ids: &[u64], is_favorite: bool) -> StorageResult<Vec<u64>> {
+        let mut changed = Vec::new();
+        for item in self.data.items.iter_mut().chain(self.session_items.iter_mut()) {
+            if item.is_favorite != is_favorite && ids.contains(&item.id) {
+                item.is_favorite = is_favorite;
+                changed.push(item.id);
+            }
+        }
+
+        if !changed.is_empty() {
+            self.mark_updated();
+        }
+        Ok(changed)
+    }
+
+    pub fn set_item_color(&mut self, id: u64, color: Option<ItemColor>) -> StorageResult<bool> {
+        if let Some(item) = self.history_item_mut(id) {
+            if item.color_label != color {
+                item.color_label = color;
+                self.mark_updated();
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // 替换条目记录的自定义格式数据
+    pub fn set_extra_formats(&mut self, id: u64, formats: Vec<ExtraFormat>) -> StorageResult<bool> {
+        if let Some(item) = self.history_item_mut(id) {
+            if item.extra_formats != formats {
+                item.extra_formats = formats;
+                self.mark_updated();
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // 设置或清除条目备注，空白备注视为清除
+    pub fn set_item_note(&mut self, id: u64, note: Option<String>) -> StorageResult<bool> {
+        let note = note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+        if let Some(item) = self.history_item_mut(id) {
+            if item.note != note {
+                item.note = note;
+                self.mark_updated();
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    pub fn clear_all(&mut self, keep_favorites: bool) -> StorageResult<()> {
+        if keep_favorites {
+            let protected_ids: Vec<u64> = self
+                .data
+                .items
+                .iter()
+                .filter(|item| self.is_protected(item))
+                .map(|item| item.id)
+                .collect();
+            self.data.items.retain(|item| protected_ids.contains(&item.id));
+        } else {
+            self.data.items.clear();
+        }
+        self.session_items.clear();
+        self.prune_collections();
+        // 不重置 next_id：get_items_since 以 id 为游标，重新从 1 分配会让增量同步漏掉新条目
+        self.mark_updated();
+        // 清空属于破坏性操作，立即落盘
+        self.save()?;
+        Ok(())
+    }
+
+    // 删除超过 secs 秒未使用的条目，返回删除条数；keep_favorites 时保留收藏及收藏夹中的条目
+    pub fn clear_older_than(&mut self, secs: u64, keep_favorites: bool) -> StorageResult<usize> {
+        let cutoff = now_secs().saturating_sub(secs);
+        let expired: HashSet<u64> = self
+            .data
+            .items
+            .iter()
+            .filter(|item| item.last_used_at < cutoff && !(keep_favorites && self.is_protected(item)))
+            .map(|item| item.id)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        self.data.items.retain(|item| !expired.contains(&item.id));
+        self.prune_collections();
+        self.mark_updated();
+        // 与清空一样属于破坏性操作，立即落盘
+        self.save()?;
+        Ok(expired.len())
+    }
+
+    pub fn search_items(&self, query: &str) -> Vec<ClipboardItem> {
+        let query = query.to_lowercase();
+        let mut items: Vec<ClipboardItem> = self
+            .history_items()
+            .filter(|item| matches_query(item, &query))
+            .cloned()
+            .collect();
+
+        self.data.settings.sort_mode.sort(&mut items);
+        if let Some(scratchpad) = &self.data.scratchpad {
+            if matches_query(scratchpad, &query) {
+                items.insert(0, scratchpad.clone());
+            }
+        }
+        items
+    }
+
+    // 与 search_items 结果条数一致，但不克隆内容；空查询返回全部条数
+    pub fn count_search_matches(&self, query: &str) -> usize {
+        let query = query.to_lowercase();
+        self.history_items()
+            .chain(self.data.scratchpad.iter())
+            .filter(|item| matches_query(item, &query))
+            .count()
+    }
+
+    // 获取 id 大于 after_id 的条目（包括仅会话条目），按 id 即捕获先后升序排列。
+    // id 只增不减，按 id 而不是时间作游标：再次复制旧内容只刷新原条目的使用时间，不算新增，
+    // 同一秒内的多次捕获也不会被漏掉。便签单独放在 scratchpad 中返回
+    pub fn get_items_since(&self, after_id: u64) -> ItemsSince {
+        let mut items: Vec<ClipboardItem> = self
+            .history_items()
+            .filter(|item| item.id > after_id)
+            .cloned()
+            .collect();
+        items.sort_by_key(|item| item.id);
+        ItemsSince {
+            items,
+            scratchpad: self.data.scratchpad.clone(),
+            cursor: self.data.next_id - 1,
+        }
+    }
+
+    pub fn get_last_updated(&self) -> u64 {
+        self.data.last_updated
+    }
+
+    // 让下一次捕获不受大小限制
+    pub fn arm_oversize_capture(&mut self) {
+        self.oversize_once = true;
+    }
+
+    pub fn oversize_capture_armed(&self) -> bool {
+        self.oversize_once
+    }
+
+    // 距上次捕获已空闲足够久，且有新捕获或距上次维护已超过最长间隔
+    pub fn maintenance_due(&self, idle: Duration) -> bool {
+        self.last_capture_at.elapsed() >= idle
+            && (self.last_maintenance_at < self.last_capture_at
+                || self.last_maintenance_at.elapsed() >= MAINTENANCE_MAX_INTERVAL)
+    }
+
+    // 执行条数与保留天数清理，返回是否删除了条目
+    pub fn run_maintenance(&mut self) -> StorageResult<bool> {
+        let before = self.data.items.len();
+        self.enforce_item_limit()?;
+        self.enforce_age_limit()?;
+        self.last_maintenance_at = Instant::now();
+
+        let changed = self.data.items.len() != before;
+        if changed {
+            self.prune_collections();
+            self.mark_updated();
+        }
+        Ok(changed)
+    }
+
+    // 删除超过保留天数未使用的条目，收藏及收藏夹中的条目除外
+    pub fn enforce_age_limit(&mut self) -> StorageResult<()> {
+        let max_age_days = self.data.settings.max_item_age_days;
+        if max_age_days == 0 {
+            return Ok(());
+        }
+
+        let now = now_secs();
+        let cutoff = now.saturating_sub(max_age_days * 86_400);
+        let expired: Vec<u64> = self
+            .data
+            .items
+            .iter()
+            .filter(|item| item.last_used_at < cutoff && !self.is_protected(item))
+            .map(|item| item.id)
+            .collect();
+        self.data.items.retain(|item| !expired.contains(&item.id));
+        Ok(())
+    }
+
+    // 导出历史条目（不含便签和仅会话条目），按参数去掉收藏标记或其他元数据
+    pub fn export_items(&self, include_favorites: bool, include_metadata: bool) -> Vec<ClipboardItem> {
+        self.data
+            .items
+            .iter()
+            .map(|item| item.filtered(include_favorites, include_metadata))
+            .collect()
+    }
+
+    // 合并导入其他设备或备份中的条目：内容已存在的不重复添加，只合并收藏状态
+    pub fn merge_items(&mut self, incoming: Vec<ClipboardItem>) -> StorageResult<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let limit = self.data.settings.limit_for(CaptureKind::Text);
+
+        for mut item in incoming {
+            if item.is_scratchpad || item.content.trim().is_empty() || item.content.len() > limit {
+                summary.skipped += 1;
+                continue;
+            }
+
+            if let Some(existing) = self.data.items.iter_mut().find(|existing| existing.content == item.content) {
+                if item.is_favorite && !existing.is_favorite {
+                    existing.is_favorite = true;
+                    summary.favorite_merged += 1;
+                } else {
+                    summary.skipped += 1;
+                }
+                continue;
+            }
+
+            item.id = self.data.next_id;
+            self.data.next_id += 1;
+            if item.created_at == 0 {
+                item.created_at = item.last_used_at;
+            }
+            item.refresh_metadata();
+            self.data.items.push(item);
+            summary.added += 1;
+        }
+
+        if summary.added > 0 || summary.favorite_merged > 0 {
+            // 条目按使用时间先后存放，导入的旧条目需排回对应位置
+            self.data
+                .items
+                .sort_by(|a, b| a.last_used_at.cmp(&b.last_used_at).then_with(|| a.id.cmp(&b.id)));
+            self.enforce_item_limit()?;
+            self.mark_updated();
+        }
+        Ok(summary)
+    }
+
+    pub fn enforce_item_limit(&mut self) -> StorageResult<()> {
+        self.evict_over_limit(None);
+        Ok(())
+    }
+
+    // 淘汰超出 max_items 的旧条目，keep 指定的条目与收藏一样不会被淘汰
+    fn evict_over_limit(&mut self, keep: Option<u64>) {
+        let max_items = self.data.settings.max_items;
+        if self.data.items.len() <= max_items {
+            return;
+        }
+
+        // 被收藏夹引用的项目与收藏一样受保护
+        let protected: HashSet<u64> = self
+            .data
+            .collections
+            .iter()
+            .flat_map(|collection| collection.item_ids.iter().copied())
+            .chain(keep)
+            .collect();
+        let evicted: HashSet<u64> = compute_evictions(&self.data.items, max_items, &protected)
+            .into_iter()
+            .collect();
+        self.data.items.retain(|item| !evicted.contains(&item.id));
+    }
+
+    // 收藏或位于任一收藏夹中的项目不会被自动清理
+    fn is_protected(&self, item: &ClipboardItem) -> bool {
+        item.is_favorite
+            || self
+                .data
+                .collections
+                .iter()
+                .any(|collection| collection.item_ids.contains(&item.id))
+    }
+
+    // 移除收藏夹中已不存在的条目引用
+    fn prune_collections(&mut self) {
+        let items = &self.data.items;
+        let has_scratchpad = self.data.scratchpad.is_some();
+        for collection in self.data.collections.iter_mut() {
+            collection.item_ids.retain(|id| {
+                (*id == SCRATCHPAD_ID && has_scratchpad) || items.iter().any(|item| item.id == *id)
+            });
+        }
+    }
+
+    pub fn get_collections(&self) -> &[Collection] {
+        &self.data.collections
+    }
+
+    pub fn create_collection(&mut self, name: String) -> StorageResult<Collection> {
+        let id = self.data.collections.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+        let collection = Collection {
+            id,
+            name,
+            item_ids: Vec::new(),
+        };
+        self.data.collections.push(collection.clone());
+        self.mark_updated();
+        Ok(collection)
+    }
+
+    // 收藏夹或条目不存在时返回 false
+    pub fn add_to_collection(&mut self, collection_id: u64, item_id: u64) -> StorageResult<bool> {
+        if self.get_item_by_id(item_id).is_none() {
+            return Ok(false);
+        }
+        let Some(collection) = self.data.collections.iter_mut().find(|c| c.id == collection_id) else {
+            return Ok(false);
+        };
+        if !collection.item_ids.contains(&item_id) {
+            collection.item_ids.push(item_id);
+            self.mark_updated();
+        }
+        Ok(true)
+    }
+
+    pub fn remove_from_collection(&mut self, collection_id: u64, item_id: u64) -> StorageResult<bool> {
+        let Some(collection) = self.data.collections.iter_mut().find(|c| c.id == collection_id) else {
+            return Ok(false);
+        };
+        let original_len = collection.item_ids.len();
+        collection.item_ids.retain(|id| *id != item_id);
+        let removed = collection.item_ids.len() < original_len;
+        if removed {
+            self.mark_updated();
+        }
+        Ok(removed)
+    }
+
+    // 按加入顺序返回收藏夹中的条目，收藏夹不存在时返回 None
+    pub fn get_collection_items(&self, collection_id: u64) -> Option<Vec<ClipboardItem>> {
+        let collection = self.data.collections.iter().find(|c| c.id == collection_id)?;
+        Some(
+            collection
+                .item_ids
+                .iter()
+                .filter_map(|id| self.get_item_by_id(*id).cloned())
+                .collect(),
+        )
+    }
+}
+
+// 计算超出 max 条时需要淘汰的条目 id：从最旧（列表前部）开始，跳过收藏和 protected 中的条目。
+// 受保护的条目过多时返回的数量可能不足以降到 max 以下
+pub fn compute_evictions(items: &[ClipboardItem], max: usize, protected: &HashSet<u64>) -> Vec<u64> {
+    let excess = items.len().saturating_sub(max);
+    items
+        .iter()
+        .filter(|item| !item.is_favorite && !protected.contains(&item.id))
+        .take(excess)
+        .map(|item| item.id)
+        .collect()
+}
+
+// 把文本按分隔符切分为片段：换行统一为 \n，分隔符为空时默认以空行分段；
+// 片段去掉首尾空白，空片段忽略
+pub fn split_snippets(content: &str, delimiter: Option<&str>) -> Vec<String> {
+    let content = content.replace("\r\n", "\n");
+    let delimiter = match delimiter.map(|delimiter| delimiter.replace("\r\n", "\n")) {
+        Some(delimiter) if !delimiter.is_empty() => delimiter,
+        _ => "\n\n".to_string(),
+    };
+    content
+        .split(delimiter.as_str())
+        .map(str::trim)
+        .filter(|chunk| !chunk.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// 类型别名，便于在 Tauri 命令中使用；只读命令取读锁，互不阻塞
+pub type SharedStorage = Arc<RwLock<SimpleStorage>>;
+
+// 后台合并写入的最短间隔
+const AUTOSAVE_INTERVAL_MS: u64 = 300;
+
+// 连续失败达到该次数时通知一次，提醒用户历史没有被保存
+pub const SAVE_FAILURE_WARN_THRESHOLD: u32 = 3;
+
+// 启动后台落盘线程，脏数据最多每 AUTOSAVE_INTERVAL_MS 写入一次；
+// 连续失败达到阈值时调用 on_failing，参数为失败次数与最近的错误
+pub fn spawn_autosave<F>(storage: SharedStorage, on_failing: F)
+where
+    F: Fn(u32, &StorageError) + Send + 'static,
+{
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_millis(AUTOSAVE_INTERVAL_MS));
+        // 只在读锁内克隆快照，序列化与写文件在锁外进行
+        let snapshot = read_storage(&storage).snapshot();
+        if let Some(snapshot) = snapshot {
+            if let Err(e) = snapshot.write() {
+                eprintln!("自动保存剪切板数据失败: {}", e);
+                let failures = read_storage(&storage).save_failures();
+                if failures == SAVE_FAILURE_WARN_THRESHOLD {
+                    on_failing(failures, &e);
+                }
+            }
+        }
+    });
+}
+
+// 安装 panic 钩子：打印日志后尽力把未落盘的修改写入磁盘，再交给原有钩子处理
+pub fn install_panic_hook(storage: SharedStorage) {
+    static IN_HOOK: AtomicBool = AtomicBool::new(false);
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        eprintln!("应用发生 panic: {}", info);
+
+        // 保存过程中再次 panic 时不再重入，直接交给原有钩子
+        if !IN_HOOK.swap(true, Ordering::SeqCst) {
+            // 发生 panic 的线程可能正持有存储锁，只能尝试获取，不能阻塞等待
+            let guard = match storage.try_read() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            };
+            match guard {
+                Some(guard) => match guard.flush() {
+                    Ok(()) => eprintln!("panic 前的修改已保存"),
+                    Err(e) => eprintln!("panic 时保存剪切板数据失败: {}", e),
+                },
+                None => eprintln!("存储锁被占用，panic 时无法保存剪切板数据"),
+            }
+            IN_HOOK.store(false, Ordering::SeqCst);
+        }
+
+        previous(info);
+    }));
+}
+
+// 获取存储写锁；若持锁线程曾经 panic 导致锁中毒，则恢复后继续使用，避免整个应用不可用
+pub fn lock_storage(storage: &SharedStorage) -> RwLockWriteGuard<'_, SimpleStorage> {
+    match storage.write() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            eprintln!("存储锁已中毒（有线程在持锁时 panic），已恢复继续使用");
+            storage.clear_poison();
+            poisoned.into_inner()
+        }
+    }
+}
+
+// 获取存储读锁，供只读取数据的命令使用，多个读取可同时进行
+pub fn read_storage(storage: &SharedStorage) -> RwLockReadGuard<'_, SimpleStorage> {
+    match storage.read() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            eprintln!("存储锁已中毒（有线程在持锁时 panic），已恢复继续使用");
+            storage.clear_poison();
+            poisoned.into_inner()
+        }
+    }
+}
+
+#[cfg(test)]
+impl SimpleStorage {
+    // 数据文件放在独立的临时目录，测试不读写真实的剪切板历史
+    pub(crate) fn in_temp_dir() -> Self {
+        static NEXT_DIR: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "clipper-test-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&dir).expect("创建测试目录失败");
+        Self::from_parts(dir.join(DATA_FILE_NAME), false, String::new(), ClipboardData::first_launch())
+    }
+
+    pub(crate) fn remove_temp_dir(&self) {
+        if let Some(dir) = self.file_path.parent() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_storage() -> SimpleStorage {
+        SimpleStorage::in_temp_dir()
+    }
+
+    fn cleanup(storage: SimpleStorage) {
+        storage.remove_temp_dir();
+    }
+
+    fn assert_advances<T>(storage: &mut SimpleStorage, name: &str, op: impl FnOnce(&mut SimpleStorage) -> T) -> T {
+        let before = storage.revision();
+        let result = op(storage);
+        assert!(storage.revision() > before, "{} 没有推进 revision", name);
+        assert!(storage.get_last_updated() <= now_secs(), "{} 之后 last_updated 超过了当前时间", name);
+        result
+    }
+
+    #[test]
+    fn every_mutation_advances_revision() {
+        let mut storage = test_storage();
+
+        let first = assert_advances(&mut storage, "add_item", |s| s.add_item("first".into()).unwrap());
+        let second = assert_advances(&mut storage, "add_item_from", |s| {
+            s.add_item_from("second".into(), CaptureSource::PrimarySelection).unwrap().id
+        });
+        assert_advances(&mut storage, "add_snippet", |s| s.add_snippet("snippet".into(), false, Vec::new()).unwrap());
+        assert_advances(&mut storage, "set_scratchpad", |s| s.set_scratchpad("note".into()).unwrap());
+        assert_advances(&mut storage, "set_item_favorite", |s| s.set_item_favorite(first, true).unwrap());
+        assert_advances(&mut storage, "set_items_favorite", |s| s.set_items_favorite(&[first], false).unwrap());
+        assert_advances(&mut storage, "set_item_color", |s| s.set_item_color(first, Some(ItemColor::Red)).unwrap());
+        assert_advances(&mut storage, "set_extra_formats", |s| {
+            s.set_extra_formats(first, vec![ExtraFormat { mime: "text/x-test".into(), base64: String::new() }])
+                .unwrap()
+        });
+        assert_advances(&mut storage, "set_item_note", |s| s.set_item_note(first, Some("memo".into())).unwrap());
+
+        let collection = assert_advances(&mut storage, "create_collection", |s| s.create_collection("c".into()).unwrap());
+        assert_advances(&mut storage, "add_to_collection", |s| s.add_to_collection(collection.id, first).unwrap());
+        assert_advances(&mut storage, "remove_from_collection", |s| {
+            s.remove_from_collection(collection.id, first).unwrap()
+        });
+
+        assert_advances(&mut storage, "remove_item", |s| assert!(s.remove_item(second).unwrap()));
+        assert_advances(&mut storage, "undo_last_delete", |s| assert!(s.undo_last_delete().unwrap().is_some()));
+        assert_advances(&mut storage, "merge_items", |s| {
+            s.merge_items(vec![ClipboardItem::new(0, "imported".into(), now_secs())]).unwrap()
+        });
+
+        // 一天前使用过的条目才会被按时间清理
+        storage.data.items[0].last_used_at = now_secs() - 86_400;
+        assert_advances(&mut storage, "clear_older_than", |s| assert!(s.clear_older_than(3600, false).unwrap() > 0));
+
+        storage.data.settings.max_items = 1;
+        assert_advances(&mut storage, "run_maintenance", |s| assert!(s.run_maintenance().unwrap()));
+        storage.data.settings.max_items = AppSettings::default().max_items;
+
+        storage.data.items.push(ClipboardItem::new(storage.data.next_id, String::new(), now_secs()));
+        assert_advances(&mut storage, "verify", |s| s.verify(true).unwrap());
+
+        assert_advances(&mut storage, "set_session_only", |s| s.set_session_only(true, false).unwrap());
+        assert_advances(&mut storage, "add_item (session)", |s| s.add_item("session".into()).unwrap());
+        assert_advances(&mut storage, "set_session_only (promote)", |s| s.set_session_only(false, true).unwrap());
+
+        assert_advances(&mut storage, "clear_all", |s| s.clear_all(false).unwrap());
+
+        let raw = storage.to_raw_json().unwrap();
+        assert_advances(&mut storage, "replace_from_raw_json", |s| s.replace_from_raw_json(&raw).unwrap());
+
+        assert_advances(&mut storage, "reload", |s| s.reload().unwrap());
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn items_since_uses_id_cursor() {
+        let mut storage = test_storage();
+        let old = storage.add_item("old".into()).unwrap();
+        storage.add_item("other".into()).unwrap();
+        let cursor = storage.get_items_since(0).cursor;
+
+        // 同一秒内的新捕获、仅会话条目都应返回；再次复制旧内容不算新增
+        storage.data.settings.dedup_window_secs = 60;
+        assert_eq!(storage.add_item("old".into()).unwrap(), old);
+        let fresh = storage.add_item("fresh".into()).unwrap();
+        storage.set_session_only(true, false).unwrap();
+        let session = storage.add_item("session".into()).unwrap();
+        storage.set_scratchpad("note".into()).unwrap();
+
+        let since = storage.get_items_since(cursor);
+        let ids: Vec<u64> = since.items.iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec![fresh, session]);
+        assert_eq!(since.cursor, session);
+        assert_eq!(since.scratchpad.map(|item| item.content), Some("note".to_string()));
+
+        // 清空后继续分配更大的 id，旧游标不会漏掉新条目
+        storage.set_session_only(false, false).unwrap();
+        storage.clear_all(false).unwrap();
+        let after_clear = storage.add_item("after clear".into()).unwrap();
+        assert!(after_clear > session);
+        assert_eq!(storage.get_items_since(since.cursor).items.len(), 1);
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn session_items_behave_like_history() {
+        let mut storage = test_storage();
+        storage.add_item("persisted".into()).unwrap();
+        storage.set_session_only(true, false).unwrap();
+        storage.data.settings.dedup_window_secs = 60;
+
+        let id = storage.add_item("secret token".into()).unwrap();
+        storage.add_item("another".into()).unwrap();
+        assert_eq!(storage.add_item("secret token".into()).unwrap(), id);
+        assert_eq!(storage.session_item_count(), 2);
+
+        assert_eq!(storage.search_items("secret").len(), 1);
+        assert_eq!(storage.count_search_matches("secret"), 1);
+        assert_eq!(storage.find_by_content("secret token").map(|item| item.id), Some(id));
+        assert_eq!(storage.get_items_by_ids(&[id]).len(), 1);
+        assert!(storage.set_item_favorite(id, true).unwrap());
+        assert!(storage.set_item_color(id, Some(ItemColor::Blue)).unwrap());
+        assert!(storage.set_item_note(id, Some("memo".into())).unwrap());
+        assert_eq!(storage.set_items_favorite(&[id], false).unwrap(), vec![id]);
+
+        // 会话条目不写入数据文件
+        assert!(!storage.to_raw_json().unwrap().contains("secret token"));
+
+        cleanup(storage);
+    }
+
+    // 按给出的顺序（最旧在前）生成条目，favorites 中的 id 标为收藏
+    fn items(ids: &[u64], favorites: &[u64]) -> Vec<ClipboardItem> {
+        ids.iter()
+            .map(|&id| {
+                let mut item = ClipboardItem::new(id, format!("item {}", id), 1_700_000_000 + id);
+                item.is_favorite = favorites.contains(&id);
+                item
+            })
+            .collect()
+    }
+
+    #[test]
+    fn only_transient_io_errors_are_retried() {
+        use std::io::{Error, ErrorKind};
+        let io = |kind| StorageError::Io(Error::from(kind));
+        assert!(is_transient_io_error(&io(ErrorKind::Interrupted)));
+        assert!(is_transient_io_error(&io(ErrorKind::TimedOut)));
+        assert!(!is_transient_io_error(&io(ErrorKind::PermissionDenied)));
+        assert!(!is_transient_io_error(&io(ErrorKind::NotFound)));
+        assert!(!is_transient_io_error(&StorageError::TooLarge));
+    }
+
+    #[test]
+    fn failed_write_keeps_data_dirty() {
+        let state = SaveState::default();
+        let missing = std::env::temp_dir().join("clipper-test-missing-dir").join(DATA_FILE_NAME);
+        let data = ClipboardData::first_launch();
+        assert!(state.write(&missing, &data, 1, MAX_SAVE_RETRIES).is_err());
+        assert!(state.dirty.load(Ordering::SeqCst));
+        assert_eq!(state.failures.load(Ordering::SeqCst), 1);
+        assert_eq!(*state.lock_written(), 0);
+    }
+
+    #[test]
+    fn evictions_none_within_limit() {
+        let none = HashSet::new();
+        assert!(compute_evictions(&[], 0, &none).is_empty());
+        assert!(compute_evictions(&items(&[1, 2, 3], &[]), 3, &none).is_empty());
+        assert!(compute_evictions(&items(&[1, 2], &[]), 5, &none).is_empty());
+    }
+
+    #[test]
+    fn evictions_remove_oldest_first() {
+        let none = HashSet::new();
+        assert_eq!(compute_evictions(&items(&[1, 2, 3, 4], &[]), 3, &none), vec![1]);
+        assert_eq!(compute_evictions(&items(&[1, 2, 3, 4], &[]), 1, &none), vec![1, 2, 3]);
+        assert_eq!(compute_evictions(&items(&[1, 2, 3], &[]), 0, &none), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn evictions_skip_favorites_and_protected() {
+        let none = HashSet::new();
+        assert_eq!(compute_evictions(&items(&[1, 2, 3, 4, 5], &[1, 3]), 3, &none), vec![2, 4]);
+
+        let protected = HashSet::from([2]);
+        assert_eq!(compute_evictions(&items(&[1, 2, 3, 4, 5], &[1]), 3, &protected), vec![3, 4]);
+    }
+
+    #[test]
+    fn evictions_with_all_favorites_remove_nothing() {
+        let none = HashSet::new();
+        assert!(compute_evictions(&items(&[1, 2, 3], &[1, 2, 3]), 1, &none).is_empty());
+    }
+
+    #[test]
+    fn evictions_fall_short_when_too_many_protected() {
+        // 只有 4、5 可以淘汰，不足以降到上限以下时也不会动受保护的条目
+        let protected = HashSet::from([3]);
+        assert_eq!(compute_evictions(&items(&[1, 2, 3, 4, 5], &[1, 2]), 1, &protected), vec![4, 5]);
+    }
+
+    #[test]
+    fn favorites_survive_dedup_eviction_and_clear() {
+        let mut storage = test_storage();
+        storage.data.settings.dedup_window_secs = 60;
+        let favorite = storage.add_item("keep me".into()).unwrap();
+        storage.set_item_favorite(favorite, true).unwrap();
+        let is_favorite = |storage: &SimpleStorage| storage.get_item_by_id(favorite).is_some_and(|item| item.is_favorite);
+
+        // 与最新条目重复、在去重时间窗口内重复，都沿用原条目及收藏状态
+        assert_eq!(storage.add_item("keep me".into()).unwrap(), favorite);
+        storage.add_item("other".into()).unwrap();
+        assert_eq!(storage.add_item("keep me".into()).unwrap(), favorite);
+        assert!(is_favorite(&storage));
+
+        // 超出上限时淘汰普通条目，收藏即使最旧也保留
+        storage.data.items.iter_mut().find(|item| item.id == favorite).unwrap().last_used_at -= 3600;
+        storage.data.items.sort_by_key(|item| item.last_used_at);
+        for i in 0..5 {
+            storage.add_item(format!("filler {}", i)).unwrap();
+        }
+        storage.data.settings.max_items = 2;
+        storage.enforce_item_limit().unwrap();
+        assert_eq!(storage.data.items.len(), 2);
+        assert!(is_favorite(&storage));
+        assert!(storage.run_maintenance().is_ok());
+        assert!(is_favorite(&storage));
+
+        // 按时间清理、清空时选择保留收藏
+        storage.clear_older_than(60, true).unwrap();
+        assert!(is_favorite(&storage));
+        storage.clear_all(true).unwrap();
+        assert_eq!(storage.data.items.len(), 1);
+        assert!(is_favorite(&storage));
+
+        // 明确不保留时才会删除
+        storage.clear_all(false).unwrap();
+        assert!(storage.get_item_by_id(favorite).is_none());
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn undo_keeps_restored_item_at_limit() {
+        let mut storage = test_storage();
+        storage.data.settings.max_items = 2;
+        let oldest = storage.add_item("oldest".into()).unwrap();
+        storage.data.items[0].last_used_at -= 60;
+        storage.add_item("middle".into()).unwrap();
+        storage.remove_item(oldest).unwrap();
+        storage.add_item("newest".into()).unwrap();
+
+        let restored = storage.undo_last_delete().unwrap().unwrap();
+        assert_eq!(restored.content, "oldest");
+        assert!(storage.get_item_by_id(restored.id).is_some());
+        let contents: Vec<&str> = storage.data.items.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["oldest", "newest"]);
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn scratchpad_does_not_use_history_slot() {
+        let mut storage = test_storage();
+        storage.add_item("older".into()).unwrap();
+        let latest = storage.add_item("latest".into()).unwrap();
+        storage.set_scratchpad("note".into()).unwrap();
+
+        let history = storage.get_history(1, HistoryOrder::default());
+        let ids: Vec<u64> = history.iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec![SCRATCHPAD_ID, latest]);
+        assert_eq!(storage.get_history_summaries(1, HistoryOrder::default()).len(), 2);
+        assert_eq!(storage.get_sorted_history(1, 1).len(), 1);
+        assert_eq!(storage.latest_item().map(|item| item.id), Some(latest));
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn size_limit_is_per_kind_with_default_fallback() {
+        let mut settings = AppSettings::default();
+        settings.max_content_kb.insert(CaptureKind::Text, 4);
+        settings.max_content_kb.remove(&CaptureKind::Image);
+
+        assert_eq!(settings.limit_for(CaptureKind::Text), 4 * 1024);
+        assert_eq!(settings.limit_for(CaptureKind::Image), 10 * 1024 * 1024);
+    }
+
+    #[test]
+    fn merge_skips_items_over_text_limit() {
+        let mut storage = test_storage();
+        storage.data.settings.max_content_kb.insert(CaptureKind::Text, 1);
+        let incoming = vec![
+            ClipboardItem::new(1, "small".into(), 1),
+            ClipboardItem::new(2, "x".repeat(2048), 2),
+        ];
+
+        let summary = storage.merge_items(incoming).unwrap();
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.skipped, 1);
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn pipe_allowlist_is_empty_by_default() {
+        assert!(AppSettings::default().pipe_command_allowlist.is_empty());
+    }
+
+    #[test]
+    fn capture_never_exceeds_item_limit() {
+        let mut storage = test_storage();
+        storage.data.settings.max_items = 3;
+        for i in 0..10 {
+            storage.add_item(format!("item {}", i)).unwrap();
+            assert!(storage.data.items.len() <= 3);
+        }
+        let contents: Vec<&str> = storage.data.items.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["item 7", "item 8", "item 9"]);
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn raw_json_with_invalid_settings_is_rejected() {
+        let mut storage = test_storage();
+        let id = storage.add_item("keep".into()).unwrap();
+
+        let mut data = ClipboardData::first_launch();
+        data.settings.window_opacity = 5.0;
+        let json = serde_json::to_string(&data).unwrap();
+
+        let err = storage.replace_from_raw_json(&json).unwrap_err();
+        assert_eq!(err.kind(), "invalid_input");
+        assert!(storage.get_item_by_id(id).is_some());
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn snippet_respects_item_limit_and_session_mode() {
+        let mut storage = test_storage();
+        storage.data.settings.max_items = 3;
+        let favorite = storage.add_item("favorite".into()).unwrap();
+        storage.set_item_favorite(favorite, true).unwrap();
+        storage.add_item("b".into()).unwrap();
+        storage.add_item("c".into()).unwrap();
+
+        let snippet = storage.add_snippet("snippet".into(), false, Vec::new()).unwrap();
+        let contents: Vec<&str> = storage.data.items.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["favorite", "c", "snippet"]);
+        assert_eq!(storage.get_item_by_id(snippet).map(|item| item.source), Some(CaptureSource::Snippet));
+
+        storage.data.settings.session_only = true;
+        let session = storage.add_snippet("session snippet".into(), false, Vec::new()).unwrap();
+        assert!(storage.data.items.iter().all(|item| item.id != session.id));
+        assert!(storage.session_items.iter().any(|item| item.id == session.id));
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn split_snippets_handles_delimiters() {
+        // 默认以空行分段，CRLF 与 LF 混用时同样生效
+        assert_eq!(split_snippets("a\r\nb\r\n\r\nc\n\n\n\nd", None), vec!["a\nb", "c", "d"]);
+        // 空分隔符等同于默认
+        assert_eq!(split_snippets("a\n\nb", Some("")), vec!["a", "b"]);
+        // 按行切分时 CRLF 不会留下 \r
+        assert_eq!(split_snippets("one\r\ntwo\n\nthree", Some("\n")), vec!["one", "two", "three"]);
+        // 分隔符本身写成 CRLF 也能匹配
+        assert_eq!(split_snippets("x---\r\ny", Some("---\r\n")), vec!["x", "y"]);
+        assert!(split_snippets(" \n\n \r\n", None).is_empty());
+    }
+
+    #[test]
+    fn import_snippets_reports_skipped_chunks() {
+        let mut storage = test_storage();
+        storage.data.settings.max_content_kb.insert(CaptureKind::Text, 1);
+        let chunks = vec!["short".to_string(), "x".repeat(2048), "another".to_string()];
+
+        let summary = storage.import_snippets(chunks, true);
+        assert_eq!(summary.added, 2);
+        assert_eq!(summary.skipped, 1);
+        assert!(storage.data.items.iter().all(|item| item.is_favorite));
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn import_snippets_respects_item_limit() {
+        let mut storage = test_storage();
+        storage.data.settings.max_items = 3;
+        let chunks = (0..10).map(|i| format!("snippet {}", i)).collect();
+
+        let summary = storage.import_snippets(chunks, false);
+        assert_eq!(summary.added, 10);
+        assert_eq!(storage.data.items.len(), 3);
+
+        cleanup(storage);
+    }
+
+    #[test]
+    fn oversize_override_survives_small_captures() {
+        let mut storage = test_storage();
+        storage.data.settings.max_content_kb.insert(CaptureKind::Text, 1);
+        storage.arm_oversize_capture();
+
+        let small = storage.add_item_from("small".into(), CaptureSource::Clipboard).unwrap();
+        assert!(!small.oversize);
+        assert!(storage.oversize_capture_armed());
+
+        let large = storage.add_item_from("x".repeat(2048), CaptureSource::Clipboard).unwrap();
+        assert!(large.oversize);
+        assert!(!storage.oversize_capture_armed());
+        assert!(matches!(
+            storage.add_item_from("y".repeat(2048), CaptureSource::Clipboard),
+            Err(StorageError::TooLarge)
+        ));
+
+        cleanup(storage);
+    }
+}
@@ -0,0 +1,241 @@
+use serde::Deserialize;
+use std::time::Duration;
+use url::Url;
+
+// 复制时对内容进行的转换
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyTransform {
+    Plain,
+    // 将链接转换为 [标题](链接)
+    MarkdownLink,
+}
+
+// 粘贴前对文本的规范化处理，可按顺序组合，不修改已保存的条目
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextTransform {
+    Trim,
+    // 将多行内容合并为一行，行之间用空格分隔
+    SingleLine,
+    Lowercase,
+    Uppercase,
+}
+
+pub fn apply_text_transforms(text: &str, transforms: &[TextTransform]) -> String {
+    transforms
+        .iter()
+        .fold(text.to_string(), |text, transform| match transform {
+            TextTransform::Trim => text.trim().to_string(),
+            TextTransform::SingleLine => text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+            TextTransform::Lowercase => text.to_lowercase(),
+            TextTransform::Uppercase => text.to_uppercase(),
+        })
+}
+
+// 抓取网页标题的超时时间与最大读取量
+const TITLE_FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+const TITLE_FETCH_MAX_BYTES: usize = 256 * 1024;
+
+pub async fn apply_copy_transform(
+    content: &str,
+    transform: CopyTransform,
+    fetch_titles: bool,
+) -> Result<String, String> {
+    match transform {
+        CopyTransform::Plain => Ok(content.to_string()),
+        CopyTransform::MarkdownLink => {
+            let url = parse_web_url(content)
+                .ok_or_else(|| crate::i18n::t("error.not_a_url").to_string())?;
+
+            // 未开启抓取或抓取失败时，退回到原始链接
+            let title = if fetch_titles {
+                fetch_page_title(&url).await
+            } else {
+                None
+            };
+
+            Ok(markdown_link(&url, title.as_deref()))
+        }
+    }
+}
+
+// 有标题时生成 [标题](链接)，否则只返回链接本身
+fn markdown_link(url: &Url, title: Option<&str>) -> String {
+    match title {
+        Some(title) => format!("[{}]({})", escape_markdown_text(title), url),
+        None => url.to_string(),
+    }
+}
+
+// 仅识别 http/https 链接
+pub fn parse_web_url(content: &str) -> Option<Url> {
+    let url = Url::parse(content.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+}
+
+async fn fetch_page_title(url: &Url) -> Option<String> {
+    let client = reqwest::Client::builder()
+        .timeout(TITLE_FETCH_TIMEOUT)
+        .build()
+        .ok()?;
+    let mut response = client.get(url.clone()).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+
+    // 标题通常位于页面开头，只读取有限长度；单个数据块可能很大，追加前先截到剩余额度
+    let mut body = Vec::new();
+    while let Ok(Some(chunk)) = response.chunk().await {
+        let room = TITLE_FETCH_MAX_BYTES - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() >= TITLE_FETCH_MAX_BYTES {
+            break;
+        }
+    }
+
+    extract_title(&String::from_utf8_lossy(&body))
+}
+
+fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = decode_html_entities(&html[start..end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+// 单遍解码标题中的 HTML 实体，避免 `&amp;lt;` 被解码两次；
+// 支持常用的命名实体与全部数字实体（如 `&#39;`、`&#x27;`），其余命名实体原样保留
+fn decode_html_entities(text: &str) -> String {
+    // 实体名最长按 10 个字符处理，避免把普通的 & 与很远处的分号配对
+    const MAX_ENTITY_LEN: usize = 10;
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&len| len <= MAX_ENTITY_LEN)
+            .and_then(|len| decode_entity(&rest[1..=len]).map(|ch| (ch, len + 2)));
+        match entity {
+            Some((ch, consumed)) => {
+                decoded.push(ch);
+                rest = &rest[consumed..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "amp" => Some('&'),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix('x').or_else(|| number.strip_prefix('X')) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn escape_markdown_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use TextTransform::*;
+
+    #[test]
+    fn single_transforms() {
+        let text = "  Hello\n  World  ";
+        assert_eq!(apply_text_transforms(text, &[Trim]), "Hello\n  World");
+        assert_eq!(apply_text_transforms(text, &[SingleLine]), "Hello World");
+        assert_eq!(apply_text_transforms(text, &[Lowercase]), "  hello\n  world  ");
+        assert_eq!(apply_text_transforms(text, &[Uppercase]), "  HELLO\n  WORLD  ");
+        assert_eq!(apply_text_transforms(text, &[]), text);
+    }
+
+    #[test]
+    fn single_line_handles_crlf_and_blank_lines() {
+        assert_eq!(apply_text_transforms("a\r\nb\r\n", &[SingleLine]), "a b");
+        assert_eq!(apply_text_transforms("a\r\n\r\n  b\n\nc \r\n", &[SingleLine]), "a b c");
+        assert!(!apply_text_transforms("x\r\ny", &[SingleLine]).contains('\r'));
+    }
+
+    #[test]
+    fn transforms_apply_in_order() {
+        let text = " Foo\r\nBAR ";
+        assert_eq!(apply_text_transforms(text, &[SingleLine, Lowercase]), "foo bar");
+        assert_eq!(apply_text_transforms(text, &[Uppercase, Trim]), "FOO\r\nBAR");
+        // 大小写转换按顺序覆盖
+        assert_eq!(apply_text_transforms(text, &[Uppercase, Lowercase, SingleLine]), "foo bar");
+    }
+
+    #[test]
+    fn markdown_link_escapes_title_and_falls_back_to_url() {
+        let url = parse_web_url(" https://example.com/a?b=1 ").unwrap();
+        assert_eq!(
+            markdown_link(&url, Some("[Docs] Page")),
+            "[\\[Docs\\] Page](https://example.com/a?b=1)"
+        );
+        assert_eq!(markdown_link(&url, None), "https://example.com/a?b=1");
+        assert!(parse_web_url("ftp://example.com").is_none());
+        assert!(parse_web_url("not a url").is_none());
+    }
+
+    #[tokio::test]
+    async fn copy_transforms_without_fetching() {
+        let link = apply_copy_transform("https://example.com", CopyTransform::MarkdownLink, false).await;
+        assert_eq!(link.as_deref(), Ok("https://example.com/"));
+        let plain = apply_copy_transform(" text ", CopyTransform::Plain, false).await;
+        assert_eq!(plain.as_deref(), Ok(" text "));
+        assert!(apply_copy_transform("text", CopyTransform::MarkdownLink, false).await.is_err());
+    }
+
+    #[test]
+    fn decodes_named_and_numeric_entities() {
+        assert_eq!(decode_html_entities("Tom &amp; Jerry&#39;s &#x27;Show&#X27;"), "Tom & Jerry's 'Show'");
+        assert_eq!(decode_html_entities("&lt;b&gt; &quot;x&quot; &#20013;&#x6587;"), "<b> \"x\" 中文");
+        // 只解码一次
+        assert_eq!(decode_html_entities("&amp;lt;"), "&lt;");
+        // 无法识别的实体与孤立的 & 原样保留
+        assert_eq!(decode_html_entities("&copy; a & b; &#xZZ; &"), "&copy; a & b; &#xZZ; &");
+    }
+
+    #[test]
+    fn extracts_title_case_insensitively() {
+        let html = "<html><HEAD><Title lang=\"en\">\n  Rust &amp;\n Cargo </TITLE></head>";
+        assert_eq!(extract_title(html).as_deref(), Some("Rust & Cargo"));
+        assert_eq!(extract_title("<title>   </title>"), None);
+        assert_eq!(extract_title("<p>no title</p>"), None);
+    }
+}
@@ -29,7 +29,7 @@ function App() {
   const [showRestartConfirm, setShowRestartConfirm] = useState(false);
   const [currentPage, setCurrentPage] = useState<'history' | 'settings'>('history');
   const [clipboardMonitoringEnabled, setClipboardMonitoringEnabled] = useState(false);
-  const [lastUpdateTime, setLastUpdateTime] = useState<number>(0);
+  const [lastRevision, setLastRevision] = useState<number>(0);
   const [contextMenu, setContextMenu] = useState<ContextMenuState>({
     visible: false,
     x: 0,
@@ -110,10 +110,10 @@ function App() {
     if (process.env.NODE_ENV === 'development') return;
 
     try {
-      const currentLastUpdated = await invoke<number>('get_last_updated');
-      if (currentLastUpdated > lastUpdateTime) {
+      const currentRevision = await invoke<number>('get_revision');
+      if (currentRevision > lastRevision) {
         console.log('检测到剪切板数据更新，重新加载...');
-        setLastUpdateTime(currentLastUpdated);
+        setLastRevision(currentRevision);
         loadClipboardHistory();
       }
     } catch (error) {
@@ -125,12 +125,12 @@ function App() {
   const loadClipboardHistory = async () => {
     setIsLoading(true);
     try {
-      const [history, lastUpdated] = await Promise.all([
+      const [history, revision] = await Promise.all([
         invoke<ClipboardItem[]>("get_clipboard_history", { limit: 100 }),
-        invoke<number>("get_last_updated")
+        invoke<number>("get_revision")
       ]);
       setClipboardHistory(history);
-      setLastUpdateTime(lastUpdated);
+      setLastRevision(revision);
     } catch (error) {
       console.error("加载剪切板历史失败:", error);
     } finally {
@@ -351,7 +351,7 @@ function App() {
     }, 3000); // 每3秒检查一次
 
     return () => clearInterval(checkInterval);
-  }, [lastUpdateTime]);
+  }, [lastRevision]);
 
   // 处理搜索
   useEffect(() => {
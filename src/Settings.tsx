@@ -171,7 +171,8 @@ function Settings({ onRequestRestart, onClose }: SettingsProps) {
   const clearAllHistory = async () => {
     setClearLoading(true);
     try {
-      await invoke("clear_all_history");
+      const token = await invoke<string>("request_clear_history");
+      await invoke("clear_all_history", { token });
       alert("所有剪切板历史已清空");
       setShowClearConfirm(false);
     } catch (error) {